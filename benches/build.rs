@@ -0,0 +1,122 @@
+//! Builds a generated blog with a single thread and with all available
+//! threads, comparing build times and checking that both produce the same
+//! index.
+//!
+//! Number of posts can be set with `BENCH_POSTS` (default 3000):
+//!
+//! ```sh
+//! BENCH_POSTS=5000 cargo bench --bench build
+//! ```
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_POSTS: usize = 3000;
+
+const WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua",
+];
+
+fn sentence(seed: usize, words: usize) -> String {
+    let mut result = String::new();
+    for i in 0..words {
+        if i > 0 {
+            result.push(' ');
+        }
+        result.push_str(WORDS[(seed * 31 + i * 7) % WORDS.len()]);
+    }
+    result
+}
+
+fn post(n: usize, count: usize) -> String {
+    let mut result = String::new();
+    let _ = writeln!(result, "---");
+    let _ = writeln!(result, "title: Post number {}", n);
+    let _ = writeln!(result, "date: 2020-01-01T00:00:00Z");
+    let _ = writeln!(result, "tags: [tag-{}, common]", n % 10);
+    let _ = writeln!(result, "---\n");
+
+    for section in 0..5 {
+        let _ = writeln!(result, "## Section {}\n", section);
+        for paragraph in 0..3 {
+            let _ = writeln!(
+                result,
+                "{} *{}* **{}**, see [previous](./post-{}.md#section-{}).\n",
+                sentence(n + paragraph, 40),
+                sentence(n + 1, 3),
+                sentence(n + 2, 2),
+                (n + count - 1) % count,
+                section
+            );
+        }
+        let _ = writeln!(result, "- {}\n- {}\n- `{}`\n", sentence(n, 5), sentence(n + 3, 6), n);
+        let _ = writeln!(result, "```rust\nfn post_{}() -> usize {{\n    {}\n}}\n```\n", n, n);
+        let _ = writeln!(result, "> [!NOTE]\n> {}\n", sentence(n + section, 12));
+    }
+    result
+}
+
+fn generate(root: &Path, count: usize) {
+    let posts = root.join("posts");
+    let _ = std::fs::remove_dir_all(root);
+    std::fs::create_dir_all(&posts).expect("unable to create corpus directory");
+    for n in 0..count {
+        std::fs::write(posts.join(format!("post-{}.md", n)), post(n, count))
+            .expect("unable to write post");
+    }
+}
+
+fn build(root: &Path, out: &str, jobs: Option<usize>) -> (Duration, PathBuf) {
+    let target = root.join(out);
+    let mut command = Command::new(env!("CARGO_BIN_EXE_blog-parser"));
+    command
+        .arg("-w")
+        .arg(root.join("posts"))
+        .arg("-o")
+        .arg(&target)
+        .args(["--now", "2024-01-01"]);
+    if let Some(jobs) = jobs {
+        command.arg("--jobs").arg(jobs.to_string());
+    }
+    command.arg("build");
+
+    let start = Instant::now();
+    let status = command.status().expect("unable to run blog-parser");
+    let elapsed = start.elapsed();
+    assert!(status.success(), "build failed: {}", status);
+    (elapsed, target)
+}
+
+fn main() {
+    let count = std::env::var("BENCH_POSTS")
+        .ok()
+        .and_then(|it| it.parse().ok())
+        .unwrap_or(DEFAULT_POSTS);
+    let root = std::env::temp_dir().join("blog-parser-bench");
+
+    println!("generating {} posts in {}", count, root.display());
+    generate(&root, count);
+
+    let (sequential, sequential_out) = build(&root, "out-sequential", Some(1));
+    println!("sequential:           {:>8.2?}", sequential);
+    let (parallel, parallel_out) = build(&root, "out-parallel", None);
+    println!(
+        "parallel ({:>3} threads): {:>8.2?} ({:.2}x)",
+        std::thread::available_parallelism().map(|it| it.get()).unwrap_or(1),
+        parallel,
+        sequential.as_secs_f64() / parallel.as_secs_f64()
+    );
+
+    let read_index = |out: &Path| std::fs::read(out.join("index.json")).expect("missing index");
+    assert!(
+        read_index(&sequential_out) == read_index(&parallel_out),
+        "sequential and parallel builds produced different indices"
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+}
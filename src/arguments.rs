@@ -1,6 +1,6 @@
 use std::{ops::Deref, path::PathBuf, str::FromStr};
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use regex::Regex;
 
 use crate::error::{BlogError, UserError};
@@ -68,19 +68,100 @@ pub struct Args {
     #[arg(short = 'o', long = "output-dir", default_value = "./out")]
     pub target_dir: PathBuf,
 
-    /// Generated file extension
-    #[arg(short = 'e', long = "file-extension", default_value = "html")]
-    pub ext: String,
+    /// Directory with templates overriding built-in ones [default: <work-dir>/templates]
+    #[arg(short = 't', long = "templates")]
+    pub templates: Option<PathBuf>,
 
-    /// Print output to stdout instead of file
+    /// Generated file extension [default: based on output format]
+    #[arg(short = 'e', long = "file-extension")]
+    pub ext: Option<String>,
+
+    /// Format of generated pages [default: svelte if extension is svelte, html
+    /// otherwise]
+    #[arg(long = "format", value_enum, global = true)]
+    pub format: Option<OutputFormat>,
+
+    /// Print generated pages to stdout instead of writing them
     #[arg(long = "stdout", default_value_t = false)]
     pub print_output: bool,
 
+    /// Time used to decide which posts are published (RFC 3339, date or local
+    /// time in blog timezone); defaults to current time
+    #[arg(long = "now")]
+    pub now: Option<String>,
+
+    /// Fail the build if any warnings are reported
+    #[arg(long = "strict", default_value_t = false)]
+    pub strict: bool,
+
+    /// Number of posts processed in parallel [default: number of CPUs]
+    #[arg(short = 'j', long = "jobs")]
+    pub jobs: Option<usize>,
+
+    /// Only report errors and the build summary
+    #[arg(short = 'q', long = "quiet", conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Log each processed post instead of showing progress bars
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+
+    /// Format of reported errors and warnings
+    #[arg(long = "message-format", value_enum, default_value_t = MessageFormat::Human)]
+    pub message_format: MessageFormat,
+
     /// Action to perform
     #[command(subcommand)]
     pub verb: Verb,
 }
 
+impl Args {
+    pub fn format(&self) -> OutputFormat {
+        match (self.format, self.ext.as_deref()) {
+            (Some(it), _) => it,
+            (None, Some("svelte")) => OutputFormat::Svelte,
+            (None, _) => OutputFormat::Html,
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        self.ext.as_deref().unwrap_or(self.format().extension())
+    }
+
+    /// Returns true if generated output is printed to stdout.
+    pub fn prints_output(&self) -> bool {
+        self.print_output || matches!(self.verb, Verb::Render(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Pages rendered with article and layout templates
+    Html,
+    /// Svelte components exporting post metadata
+    Svelte,
+    /// JSON component tree of each post
+    Ast,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Html => "html",
+            OutputFormat::Svelte => "svelte",
+            OutputFormat::Ast => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// Messages with source snippets printed to stderr
+    Human,
+    /// JSON object per line printed to stdout
+    Json,
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Verb {
     /// Clones remote blog repository to local path
@@ -93,6 +174,8 @@ pub enum Verb {
     Watch,
     /// Builds metadata files and pages
     Build,
+    /// Renders a single post to stdout
+    Render(RenderArgs),
     /// Print a list of posts for query
     Posts, // PostQuery
     /// Mark post published and push it
@@ -109,3 +192,40 @@ pub struct GitSource {
     #[arg(short = 'b', long = "branch", default_value = "master")]
     pub repo_branch: String,
 }
+
+#[derive(Debug, Parser)]
+pub struct RenderArgs {
+    /// Markdown file to render, or `-` to read it from stdin
+    pub input: PathBuf,
+
+    /// Template HTML output is rendered with
+    #[arg(long = "template", value_enum, default_value_t = RenderTemplate::Article)]
+    pub template: RenderTemplate,
+
+    /// Format of rendered output
+    #[arg(long = "as", value_enum, default_value_t = RenderFormat::Html)]
+    pub output: RenderFormat,
+
+    /// Print only post metadata read from frontmatter, as JSON
+    #[arg(long = "frontmatter-only", default_value_t = false)]
+    pub frontmatter_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RenderTemplate {
+    /// Article template, wrapped in post layout if one is configured
+    Article,
+    /// No template; only the rendered post content
+    #[value(alias = "fragment")]
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RenderFormat {
+    /// HTML page or fragment
+    Html,
+    /// JSON component tree
+    Ast,
+    /// Text content without markup
+    Text,
+}
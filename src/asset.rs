@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use git2::Repository;
+
+use crate::{config::CONFIG_FILE, error::FormatError, schema::SCHEMA_FILE};
+
+/// Extensions of link targets treated as pages rather than assets.
+const PAGE_EXTENSIONS: &[&str] = &["md", "html", "htm"];
+
+/// Lexically normalizes `path`, resolving `.` and `..` components.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for it in path.components() {
+        match it {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            other => result.push(other),
+        }
+    }
+    result
+}
+
+/// Returns true if `url` refers to a location relative to the document it's
+/// in, i.e. it has no scheme and doesn't start with `/`, `#` or `?`.
+pub fn is_relative(url: &str) -> bool {
+    if url.is_empty() || url.starts_with(['/', '#', '?']) {
+        return false;
+    }
+    let path_end = url.find(['/', '?', '#']).unwrap_or(url.len());
+    !url[..path_end].contains(':')
+}
+
+/// Splits `url` into path and the query/fragment suffix.
+pub fn split_suffix(url: &str) -> (&str, &str) {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    url.split_at(end)
+}
+
+/// Returns true if relative `path` points to a page rather than an asset;
+/// links to pages are left as is.
+pub fn is_page(path: &str) -> bool {
+    match Path::new(path).extension().and_then(|it| it.to_str()) {
+        Some(ext) => PAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()),
+        None => true,
+    }
+}
+
+/// Resolves a relative reference from `source` file to a path relative to
+/// blog `root`.
+///
+/// Returns `None` if the referenced path is outside of `root`.
+pub fn resolve(root: &Path, source: &Path, path: &str) -> Option<PathBuf> {
+    let dir = source.parent().unwrap_or(Path::new(""));
+    let full = normalize(&dir.join(path));
+    full.strip_prefix(normalize(root))
+        .ok()
+        .map(Path::to_path_buf)
+}
+
+/// Returns root relative URL of an asset at `relative` path.
+pub fn url(relative: &Path) -> String {
+    format!("/{}", relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Inserts `hash` before the extension of `relative` path, e.g.
+/// `static/style.<hash>.css`.
+pub fn fingerprinted(relative: &Path, hash: &str) -> PathBuf {
+    let stem = relative.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match relative.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, hash, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, hash),
+    };
+    relative.with_file_name(name)
+}
+
+/// Files that are part of blog configuration rather than its content.
+fn is_config(relative: &Path) -> bool {
+    relative == Path::new(CONFIG_FILE) || relative == Path::new(SCHEMA_FILE)
+}
+
+/// Collects paths (relative to `root`) of non-Markdown files that should be
+/// copied to the output.
+///
+/// Hidden files, configuration, anything under `exclude` directories (e.g.
+/// templates) and files ignored by git are skipped.
+pub fn collect(root: &Path, exclude: &[PathBuf]) -> Vec<PathBuf> {
+    let repo = Repository::discover(root).ok();
+    let workdir = repo
+        .as_ref()
+        .and_then(|it| it.workdir())
+        .and_then(|it| it.canonicalize().ok());
+    let exclude: Vec<PathBuf> = exclude.iter().filter_map(|it| it.canonicalize().ok()).collect();
+
+    let is_ignored = |path: &Path| {
+        let (repo, workdir) = match (&repo, &workdir) {
+            (Some(repo), Some(workdir)) => (repo, workdir),
+            _ => return false,
+        };
+        path.canonicalize()
+            .ok()
+            .and_then(|it| it.strip_prefix(workdir).ok().map(Path::to_path_buf))
+            .map(|it| repo.is_path_ignored(it).unwrap_or_default())
+            .unwrap_or_default()
+    };
+
+    let mut result = vec![];
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(it) => it,
+            Err(err) => {
+                log::warn!("unable to read directory {}: {}", dir.display(), err);
+                continue;
+            }
+        };
+        for entry in entries.filter_map(|it| it.ok()) {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden || is_ignored(&path) {
+                continue;
+            }
+            let file_type = match entry.file_type() {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            // directory symlinks aren't followed as they can form loops
+            if file_type.is_symlink() && path.is_dir() {
+                log::debug!("skipping directory symlink {}", path.display());
+                continue;
+            }
+            if file_type.is_dir() {
+                let excluded = path
+                    .canonicalize()
+                    .map(|it| exclude.contains(&it))
+                    .unwrap_or_default();
+                if !excluded {
+                    pending.push(path);
+                }
+                continue;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            let markdown = path.extension().map(|it| it == "md").unwrap_or_default();
+            if !markdown && !is_config(&relative) {
+                result.push(relative);
+            }
+        }
+    }
+
+    result.sort();
+    result
+}
+
+/// Copies `assets` (relative to `root`) into `target_dir`, keeping their
+/// relative paths.
+pub fn copy(root: &Path, target_dir: &Path, assets: &[PathBuf]) -> Result<(), FormatError> {
+    for it in assets {
+        let target = target_dir.join(it);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(root.join(it), target)?;
+    }
+    Ok(())
+}
+
+/// Copies files (relative to `root`) to their fingerprinted paths in
+/// `target_dir`.
+pub fn copy_fingerprinted(
+    root: &Path,
+    target_dir: &Path,
+    assets: &HashMap<PathBuf, PathBuf>,
+) -> Result<(), FormatError> {
+    for (source, fingerprinted) in assets {
+        let target = target_dir.join(fingerprinted);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(root.join(source), target)?;
+    }
+    Ok(())
+}
@@ -10,7 +10,10 @@ use chrono::{DateTime, Utc};
 use nym::glob::Glob;
 use serde::{Deserialize, Serialize};
 
-use crate::error::{BlogError, FormatError};
+use crate::{
+    config::BlogConfig,
+    error::{BlogError, FormatError},
+};
 
 #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct IndexData {
@@ -64,6 +67,14 @@ impl IndexData {
 
         IndexData { created, modified }
     }
+
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        self.created
+    }
+
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.modified
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +123,9 @@ pub struct Blog {
 
     #[serde(skip)]
     pub file_index: Option<FileIndex>,
+
+    #[serde(skip)]
+    pub config: BlogConfig,
 }
 
 impl Blog {
@@ -119,6 +133,7 @@ impl Blog {
         Ok(Blog {
             source_dir: path.as_ref().to_path_buf(),
             file_index: None,
+            config: BlogConfig::load(path)?,
         })
     }
 
@@ -0,0 +1,296 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use super::{PostComponent, TextComponent, TextPart};
+
+/// Configuration of a single admonition kind (e.g. `note`, `warning`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmonitionKind {
+    /// Kind name as written in sources; matched case-insensitively.
+    pub name: String,
+    /// Title used when the source doesn't provide one.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Raw HTML (or text) rendered in front of the title.
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl AdmonitionKind {
+    pub fn new(name: impl ToString, icon: Option<&str>) -> Self {
+        AdmonitionKind {
+            name: name.to_string(),
+            title: None,
+            icon: icon.map(str::to_string),
+        }
+    }
+
+    pub fn default_title(&self) -> String {
+        if let Some(title) = &self.title {
+            return title.clone();
+        }
+
+        let mut chars = self.name.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+
+    /// Kinds supported by GitHub alerts.
+    pub fn defaults() -> Vec<AdmonitionKind> {
+        vec![
+            AdmonitionKind::new("note", Some("ℹ️")),
+            AdmonitionKind::new("tip", Some("💡")),
+            AdmonitionKind::new("important", Some("❗")),
+            AdmonitionKind::new("warning", Some("⚠️")),
+            AdmonitionKind::new("caution", Some("🛑")),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmonitionComponent<'a> {
+    /// Lowercase kind name, used for class names.
+    pub kind: String,
+    pub title: TextComponent<'a>,
+    /// False if `title` is the default title of the kind rather than text
+    /// written in the source.
+    #[serde(default)]
+    pub custom_title: bool,
+    pub icon: Option<String>,
+    pub content: Vec<PostComponent<'a>>,
+}
+
+impl<'a> AdmonitionComponent<'a> {
+    pub fn new(kind: &AdmonitionKind, title: Option<TextComponent<'a>>) -> Self {
+        AdmonitionComponent {
+            kind: kind.name.to_lowercase(),
+            custom_title: title.is_some(),
+            title: title.unwrap_or_else(|| TextComponent::new(kind.default_title())),
+            icon: kind.icon.clone(),
+            content: Vec::with_capacity(4),
+        }
+    }
+
+    pub fn into_owned(self) -> AdmonitionComponent<'static> {
+        AdmonitionComponent {
+            kind: self.kind,
+            title: self.title.into_owned(),
+            custom_title: self.custom_title,
+            icon: self.icon,
+            content: self.content.into_iter().map(PostComponent::into_owned).collect(),
+        }
+    }
+}
+
+/// Parses `[!KIND]` marker at the start of `text`, returning kind name and
+/// remaining text.
+fn parse_marker(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim_start().strip_prefix("[!")?;
+    let end = rest.find(']')?;
+    let name = &rest[..end];
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    Some((name, &rest[end + 1..]))
+}
+
+/// Tries to convert first line of a block quote into an admonition.
+///
+/// `first_line` must be the content of the first paragraph in the block quote
+/// up to its first line break. Returns `None` if the line doesn't start with a
+/// marker of one of configured `kinds`.
+pub fn from_marker<'a>(
+    first_line: &mut TextPart<'a>,
+    kinds: &[AdmonitionKind],
+) -> Option<AdmonitionComponent<'a>> {
+    let leading = match first_line {
+        TextPart::Raw(it) => it,
+        TextPart::Chained(items) => match items.first_mut() {
+            Some(TextPart::Raw(it)) => it,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let (name, rest) = parse_marker(leading)?;
+    let kind = kinds.iter().find(|it| it.name.eq_ignore_ascii_case(name))?;
+    let rest = rest.trim_start().to_string();
+    *leading = rest;
+
+    let title = std::mem::take(first_line);
+    let title = match &title {
+        TextPart::Raw(it) if it.trim().is_empty() => None,
+        TextPart::Chained(items)
+            if items.len() == 1 && matches!(&items[0], TextPart::Raw(it) if it.trim().is_empty()) =>
+        {
+            None
+        }
+        _ => Some(TextComponent::from(title)),
+    };
+
+    Some(AdmonitionComponent::new(kind, title))
+}
+
+/// Rewrites `:::kind title` fenced containers into GitHub style
+/// (`> [!kind] title`) block quotes so they can be handled by the same
+/// parsing logic. Containers of kinds not in `kinds` are left as they are.
+///
+/// Line count is preserved so that source positions reported by the parser
+/// still point to correct lines. Returned list contains the number of
+/// characters added before the content of each line, so columns can be mapped
+/// back to `source`; it's empty if nothing was rewritten.
+pub fn expand_containers<'s>(
+    source: &'s str,
+    kinds: &[AdmonitionKind],
+) -> (Cow<'s, str>, Vec<isize>) {
+    if !source.contains(":::") {
+        return (Cow::Borrowed(source), Vec::new());
+    }
+
+    let mut result = String::with_capacity(source.len() + 64);
+    let mut shifts = Vec::new();
+    // number of open containers that were rewritten
+    let mut depth = 0usize;
+    // whether each open container was rewritten, innermost last
+    let mut open = Vec::new();
+    let mut code_fence: Option<&str> = None;
+
+    for line in source.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let eol = &line[content.len()..];
+        let trimmed = content.trim_start();
+
+        if let Some(fence) = code_fence {
+            if trimmed.starts_with(fence) && trimmed.trim_start_matches(&fence[..1]).trim().is_empty()
+            {
+                code_fence = None;
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let fence_char = &trimmed[..1];
+            let len = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+            code_fence = Some(&trimmed[..len]);
+        } else if let Some(rest) = trimmed.strip_prefix(":::") {
+            let rest = rest.trim_start_matches(':').trim();
+            let (name, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if rest.is_empty() {
+                if open.pop() == Some(true) {
+                    depth -= 1;
+                    result.push_str(&">".repeat(depth));
+                    result.push_str(eol);
+                    shifts.push(0);
+                    continue;
+                }
+            } else if !kinds.iter().any(|it| it.name.eq_ignore_ascii_case(name)) {
+                open.push(false);
+            } else {
+                let title = title.trim();
+                let start = result.len();
+                result.push_str(&"> ".repeat(depth));
+                result.push_str("> [!");
+                result.push_str(name);
+                result.push(']');
+                if title.is_empty() {
+                    shifts.push(0);
+                } else {
+                    result.push(' ');
+                    let expanded = result[start..].chars().count() as isize;
+                    let title_start = title.as_ptr() as usize - content.as_ptr() as usize;
+                    shifts.push(expanded - content[..title_start].chars().count() as isize);
+                    result.push_str(title);
+                }
+                result.push_str(eol);
+                depth += 1;
+                open.push(true);
+                continue;
+            }
+        }
+
+        result.push_str(&"> ".repeat(depth));
+        result.push_str(line);
+        shifts.push(2 * depth as isize);
+    }
+
+    (Cow::Owned(result), shifts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers() {
+        assert_eq!(parse_marker("[!NOTE] Title"), Some(("NOTE", " Title")));
+        assert_eq!(parse_marker("  [!tip]"), Some(("tip", "")));
+        assert_eq!(parse_marker("[!]"), None);
+        assert_eq!(parse_marker("[!not a kind]"), None);
+        assert_eq!(parse_marker("[NOTE]"), None);
+    }
+
+    #[test]
+    fn marker_title() {
+        let kinds = AdmonitionKind::defaults();
+
+        let mut line = TextPart::Raw("[!Warning] Mind the gap".to_string());
+        let admonition = from_marker(&mut line, &kinds).expect("marker not recognized");
+        assert_eq!(admonition.kind, "warning");
+        assert_eq!(admonition.title.plain_text(), "Mind the gap");
+        assert!(admonition.custom_title);
+
+        let mut line = TextPart::Raw("[!NOTE]".to_string());
+        let admonition = from_marker(&mut line, &kinds).expect("marker not recognized");
+        assert_eq!(admonition.title.plain_text(), "Note");
+        assert!(!admonition.custom_title);
+    }
+
+    #[test]
+    fn unknown_marker() {
+        let mut line = TextPart::Raw("[!SPOILER] Title".to_string());
+        assert!(from_marker(&mut line, &AdmonitionKind::defaults()).is_none());
+        assert!(matches!(&line, TextPart::Raw(it) if it == "[!SPOILER] Title"));
+    }
+
+    #[test]
+    fn containers() {
+        let (result, shifts) =
+            expand_containers(":::warning Title\nText\n:::\n", &AdmonitionKind::defaults());
+        assert_eq!(result, "> [!warning] Title\n> Text\n\n");
+        assert_eq!(shifts, [2, 2, 0]);
+    }
+
+    #[test]
+    fn nested_containers() {
+        let (result, shifts) = expand_containers(
+            ":::note\n:::tip\nA\n:::\n:::\n",
+            &AdmonitionKind::defaults(),
+        );
+        assert_eq!(result, "> [!note]\n> > [!tip]\n> > A\n>\n\n");
+        assert_eq!(shifts, [0, 0, 4, 0, 0]);
+    }
+
+    #[test]
+    fn containers_in_code() {
+        let source = "```md\n:::note\n```\n~~~~\n:::\n~~~~\n";
+        let (result, _) = expand_containers(source, &AdmonitionKind::defaults());
+        assert_eq!(result, source);
+    }
+
+    #[test]
+    fn unknown_containers() {
+        let kinds = AdmonitionKind::defaults();
+        let source = ":::spoiler\nA\n:::\n";
+        assert_eq!(expand_containers(source, &kinds).0, source);
+
+        let (result, _) = expand_containers(":::note\n:::spoiler\nA\n:::\n:::\n", &kinds);
+        assert_eq!(result, "> [!note]\n> :::spoiler\n> A\n> :::\n\n");
+    }
+
+    #[test]
+    fn no_containers() {
+        let (result, shifts) = expand_containers("Text\n", &AdmonitionKind::defaults());
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert!(shifts.is_empty());
+    }
+}
@@ -3,9 +3,10 @@ use std::{
     fmt::{Debug, Write},
 };
 
+use serde::{Deserialize, Serialize};
 use strum::EnumDiscriminants;
 
-use crate::util::random_id;
+use crate::{images::ResponsiveImage, util::random_id};
 
 pub mod text;
 pub use text::*;
@@ -13,10 +14,20 @@ pub use text::*;
 pub mod tex;
 pub use tex::LatexComponent;
 
+pub mod admonition;
+pub use admonition::AdmonitionComponent;
+
 pub mod parser;
 pub use parser::ComponentParser as Parser;
 
-#[derive(Debug, PartialEq, Eq)]
+pub mod svelte;
+
+pub mod plain;
+
+pub mod urls;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Alignment {
     None,
     Left,
@@ -35,19 +46,63 @@ impl From<pulldown_cmark::Alignment> for Alignment {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableComponent<'a> {
     headers: Vec<PostComponent<'a>>,
     alignment: Vec<Alignment>,
     rows: Vec<Vec<PostComponent<'a>>>,
 }
 
-#[derive(Debug)]
+impl<'a> TableComponent<'a> {
+    /// Returns header and body cells, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = &PostComponent<'a>> {
+        self.headers.iter().chain(self.rows.iter().flatten())
+    }
+
+    pub fn into_owned(self) -> TableComponent<'static> {
+        TableComponent {
+            headers: self.headers.into_iter().map(PostComponent::into_owned).collect(),
+            alignment: self.alignment,
+            rows: self
+                .rows
+                .into_iter()
+                .map(|row| row.into_iter().map(PostComponent::into_owned).collect())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListComponent<'a> {
     pub numbered: Option<usize>,
     pub items: Vec<PostComponent<'a>>,
 }
 
+impl<'a> ListComponent<'a> {
+    pub fn into_owned(self) -> ListComponent<'static> {
+        ListComponent {
+            numbered: self.numbered,
+            items: self.items.into_iter().map(PostComponent::into_owned).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Figure {
+    /// Position of the figure in the post, if figures are numbered.
+    pub number: Option<usize>,
+    /// Caption prefix preceding the number, e.g. `Figure`.
+    #[serde(default)]
+    pub label: String,
+}
+
+impl Figure {
+    /// Anchor of numbered figures, for cross-references.
+    pub fn id(&self) -> Option<String> {
+        self.number.map(|it| format!("figure-{}", it))
+    }
+}
+
 #[derive(Debug, Default)]
 pub enum Structured<T> {
     #[default]
@@ -102,23 +157,42 @@ pub trait Container<T> {
 
 pub type PostEntry = Structured<Box<dyn Component>>;
 
-#[derive(Debug, Default, EnumDiscriminants)]
+/// Component tree nodes.
+///
+/// Serialized form is part of the JSON AST output; changes to it must bump
+/// [`crate::post::AST_VERSION`].
+#[derive(Debug, Clone, Default, EnumDiscriminants, Serialize, Deserialize)]
 #[strum_discriminants(derive(strum::Display))]
 #[strum_discriminants(name(PostComponentKind))]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum PostComponent<'a> {
+    /// Only used while parsing, never part of a finished tree.
     #[default]
+    #[serde(skip)]
     Placeholder,
     Text(TextComponent<'a>),
     BlockQuote(Vec<PostComponent<'a>>),
+    Admonition(AdmonitionComponent<'a>),
     Image {
         source: String,
+        /// Plain text of the image description.
         alt: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        /// Set for titled images standing alone in a paragraph, which are
+        /// rendered as figures captioned with the title.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        figure: Option<Figure>,
+        /// Dimensions and variants of local images, added during build.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        responsive: Option<ResponsiveImage>,
     },
     CodeBlock {
         language: Option<String>,
         content: String,
     },
     List(ListComponent<'a>),
+    #[serde(rename = "horizontal_rule")]
     HorizonalRule,
     Table(TableComponent<'a>),
     Footnote {
@@ -137,15 +211,61 @@ impl<'a> PostComponent<'a> {
         PostComponentKind::from(self)
     }
 
+    /// Returns the component without data borrowed from parsed source, so
+    /// it can be kept after parsing.
+    pub fn into_owned(self) -> PostComponent<'static> {
+        match self {
+            PostComponent::Placeholder => PostComponent::Placeholder,
+            PostComponent::Text(text) => PostComponent::Text(text.into_owned()),
+            PostComponent::BlockQuote(items) => {
+                PostComponent::BlockQuote(items.into_iter().map(Self::into_owned).collect())
+            }
+            PostComponent::Admonition(admonition) => {
+                PostComponent::Admonition(admonition.into_owned())
+            }
+            PostComponent::Image {
+                source,
+                alt,
+                title,
+                figure,
+                responsive,
+            } => PostComponent::Image {
+                source,
+                alt,
+                title,
+                figure,
+                responsive,
+            },
+            PostComponent::CodeBlock { language, content } => {
+                PostComponent::CodeBlock { language, content }
+            }
+            PostComponent::List(list) => PostComponent::List(list.into_owned()),
+            PostComponent::HorizonalRule => PostComponent::HorizonalRule,
+            PostComponent::Table(table) => PostComponent::Table(table.into_owned()),
+            PostComponent::Footnote { id, text } => PostComponent::Footnote {
+                id,
+                text: text.into_owned(),
+            },
+            PostComponent::Latex(latex) => PostComponent::Latex(latex.into_owned()),
+            PostComponent::Chained(items) => {
+                PostComponent::Chained(items.into_iter().map(Self::into_owned).collect())
+            }
+            PostComponent::Raw(raw) => PostComponent::Raw(raw),
+        }
+    }
+
     pub fn push(&mut self, other: PostComponent<'a>) {
         match (self, other) {
             (current, other) if current.discriminant() == PostComponentKind::Placeholder => {
                 *current = other;
             }
             (
-                PostComponent::BlockQuote(items) | PostComponent::List(ListComponent { items, .. }),
+                PostComponent::BlockQuote(items)
+                | PostComponent::Admonition(AdmonitionComponent { content: items, .. })
+                | PostComponent::List(ListComponent { items, .. }),
                 other,
             ) => items.push(other),
+            (PostComponent::Raw(current), PostComponent::Raw(raw)) => current.push_str(&raw),
             (PostComponent::Text(text_component), PostComponent::Text(other)) => {
                 text_component.push(other)
             }
@@ -179,7 +299,10 @@ impl<'a> PostComponent<'a> {
                 *self = PostComponent::Text(TextComponent::new(text));
             }
             PostComponent::Text(component) => component.push(text.to_string()),
-            PostComponent::BlockQuote(quote) => match quote.last_mut() {
+            PostComponent::BlockQuote(quote)
+            | PostComponent::Admonition(AdmonitionComponent { content: quote, .. }) => match quote
+                .last_mut()
+            {
                 Some(PostComponent::Text(text_component)) => text_component.push(text.to_string()),
                 _ => quote.push(PostComponent::from(TextPart::from(text))),
             },
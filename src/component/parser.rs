@@ -1,19 +1,48 @@
-use std::{borrow::Cow, collections::VecDeque};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    ops::Range,
+};
 
-use pulldown_cmark::{CowStr, Event, InlineStr, Parser, Tag, TagEnd};
+use pulldown_cmark::{CowStr, Event, InlineStr, OffsetIter, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
 
 use crate::component::{
-    text::{Style, TextComponent, TextPart},
-    ListComponent, PostComponent, PostComponentKind,
+    admonition::{self, AdmonitionKind},
+    text::{Link, LinkKind, Style, TextComponent, TextPart},
+    Figure, ListComponent, PostComponent, PostComponentKind,
 };
 
 use super::TableComponent;
+use crate::{links::ExternalLinkOptions, slug::slugify};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ParserOptions {
     /// If true soft breaks produce newlines (`<br/>`) and hard breaks double
     /// newlines (`<br/><br/>`).
     pub newline_soft_break: bool,
+    /// Recognized admonition (callout) kinds. Block quotes starting with a
+    /// marker of any other kind are left as regular block quotes.
+    pub admonitions: Vec<AdmonitionKind>,
+    /// Number figures (titled images on their own line) in captions.
+    pub number_figures: bool,
+    /// Caption prefix of numbered figures.
+    pub figure_label: String,
+    /// Attributes of and restrictions on links to other sites.
+    pub external_links: ExternalLinkOptions,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            newline_soft_break: false,
+            admonitions: AdmonitionKind::defaults(),
+            number_figures: false,
+            figure_label: "Figure".to_string(),
+            external_links: ExternalLinkOptions::default(),
+        }
+    }
 }
 
 struct TableParseStage<'a> {
@@ -24,39 +53,190 @@ struct TableParseStage<'a> {
 enum ParseStage<'a> {
     None,
     Table(TableParseStage<'a>),
+    /// Frontmatter is handled by [`crate::frontmatter`], its content is skipped.
+    Metadata,
 }
 
 pub struct ComponentParser<'input> {
-    inner: Parser<'input, 'input>,
+    inner: OffsetIter<'input, 'input>,
     options: ParserOptions,
     stack: Vec<PostComponent<'input>>,
     stage: ParseStage<'input>,
+    /// Number of images being parsed; their content is alt text.
+    image_depth: usize,
+    figure_count: usize,
+    /// Ids used by headings, with the last number used to suffix them.
+    heading_ids: HashMap<String, usize>,
+    /// Destination URLs of parsed links and images, with their byte ranges
+    /// in source.
+    link_ranges: Vec<(String, Range<usize>)>,
 }
 
 impl<'input> ComponentParser<'input> {
     pub fn new(source: &'input str) -> Self {
+        Self::with_options(source, ParserOptions::default())
+    }
+
+    pub fn with_options(source: &'input str, options: ParserOptions) -> Self {
         ComponentParser {
-            inner: Parser::new(source),
-            options: ParserOptions::default(),
+            inner: Parser::new(source).into_offset_iter(),
+            options,
             stack: Vec::with_capacity(8),
             stage: ParseStage::None,
+            image_depth: 0,
+            figure_count: 0,
+            heading_ids: HashMap::new(),
+            link_ranges: Vec::new(),
+        }
+    }
+
+    /// Returns destination URLs of links and images parsed so far, with byte
+    /// ranges of their Markdown in source.
+    pub fn into_link_ranges(self) -> Vec<(String, Range<usize>)> {
+        self.link_ranges
+    }
+
+    /// Collects content of an open image as plain alt text.
+    ///
+    /// Returns false for the event closing the outermost image, which is
+    /// handled like any other end tag.
+    fn push_alt(&mut self, event: &Event) -> bool {
+        let text = match event {
+            Event::Start(Tag::Image { .. }) => {
+                self.image_depth += 1;
+                return true;
+            }
+            Event::End(TagEnd::Image) => {
+                self.image_depth -= 1;
+                return self.image_depth > 0;
+            }
+            Event::Text(text) | Event::Code(text) => text.as_ref(),
+            Event::SoftBreak | Event::HardBreak => " ",
+            _ => return true,
+        };
+        if let Some(PostComponent::Image { alt, .. }) = self.stack.last_mut() {
+            alt.get_or_insert_with(String::new).push_str(text);
+        }
+        true
+    }
+
+    /// Returns `id`, suffixed with a number if it's already used by an
+    /// earlier heading.
+    fn unique_id(&mut self, id: &str) -> String {
+        let mut count = self.heading_ids.get(id).copied().unwrap_or_default();
+        let mut unique = id.to_string();
+        // suffixed ids can also be taken by headings like "Intro 1"
+        while self.heading_ids.contains_key(&unique) {
+            count += 1;
+            unique = format!("{}-{}", id, count);
+        }
+        self.heading_ids.insert(id.to_string(), count);
+        self.heading_ids.entry(unique.clone()).or_default();
+        unique
+    }
+
+    /// Assigns an id derived from heading text to a closed `heading` without
+    /// one.
+    fn heading_id(&mut self, heading: &mut PostComponent<'input>) {
+        if let PostComponent::Text(text) = heading {
+            if let Style::Heading { id: None, .. } = text.style {
+                let mut slug = slugify(text.plain_text());
+                if slug.is_empty() {
+                    slug = "section".to_string();
+                }
+                let id = self.unique_id(&slug);
+                if let Style::Heading { id: style_id, .. } = &mut text.style {
+                    *style_id = Some(Cow::Owned(id));
+                }
+            }
+        }
+    }
+
+    /// Unwraps images standing alone in a closed `paragraph`, turning titled
+    /// ones into figures.
+    fn standalone_image(&mut self, paragraph: PostComponent<'input>) -> PostComponent<'input> {
+        let mut image = match paragraph {
+            PostComponent::Chained(mut items)
+                if items.len() == 2
+                    && matches!(&items[0], PostComponent::Text(TextComponent {
+                        style: Style::Paragraph,
+                        content: TextPart::Empty,
+                    }))
+                    && matches!(&items[1], PostComponent::Image { .. }) =>
+            {
+                items.pop().unwrap_or_default()
+            }
+            other => return other,
+        };
+
+        if let PostComponent::Image {
+            title: Some(_),
+            figure,
+            ..
+        } = &mut image
+        {
+            let number = if self.options.number_figures {
+                self.figure_count += 1;
+                Some(self.figure_count)
+            } else {
+                None
+            };
+            *figure = Some(Figure {
+                number,
+                label: self.options.figure_label.clone(),
+            });
         }
+        image
+    }
+
+    /// Converts a block quote on the stack into an admonition if the first
+    /// line of its first paragraph is an admonition marker (`[!NOTE]`).
+    ///
+    /// Should be called at the end of first paragraph line; `closed` indicates
+    /// that the paragraph ended as well.
+    fn open_admonition(&mut self, closed: bool) -> bool {
+        let len = self.stack.len();
+        if len < 2 {
+            return false;
+        }
+        let (quote, paragraph) = match &mut self.stack[len - 2..] {
+            [PostComponent::BlockQuote(items), PostComponent::Text(paragraph)]
+                if items.is_empty() && paragraph.style == Style::Paragraph =>
+            {
+                (len - 2, paragraph)
+            }
+            _ => return false,
+        };
+
+        let admonition =
+            match admonition::from_marker(&mut paragraph.content, &self.options.admonitions) {
+                Some(it) => it,
+                None => return false,
+            };
+
+        self.stack[quote] = PostComponent::Admonition(admonition);
+        if closed {
+            self.stack.pop();
+        }
+        true
     }
 
     #[inline]
     fn push_cm_start(&mut self, tag: Tag<'input>) {
         match tag {
-            Tag::MetadataBlock(_kind) => unimplemented!(),
+            Tag::MetadataBlock(_) => self.stage = ParseStage::Metadata,
             Tag::Paragraph => self
                 .stack
                 .push(PostComponent::Text(TextComponent::new_styled(
                     Style::Paragraph,
                 ))),
-            Tag::Heading { level, .. } => {
+            Tag::Heading { level, id, .. } => {
+                let mut style = Style::from(level);
+                if let (Style::Heading { id: style_id, .. }, Some(id)) = (&mut style, id) {
+                    *style_id = Some(Cow::Owned(self.unique_id(&id)));
+                }
                 self.stack
-                    .push(PostComponent::Text(TextComponent::new_styled(Style::from(
-                        level,
-                    ))))
+                    .push(PostComponent::Text(TextComponent::new_styled(style)))
             }
             Tag::BlockQuote => self.stack.push(PostComponent::BlockQuote(vec![])),
             Tag::CodeBlock(kind) => self.stack.push(PostComponent::CodeBlock {
@@ -104,23 +284,38 @@ impl<'input> ComponentParser<'input> {
                 style: Style::Strikethrough,
                 ..Default::default()
             })),
-            // TODO: Handle link types
             Tag::Link {
-                dest_url, title, ..
-            } => self.stack.push(PostComponent::Text(TextComponent::new_link(
-                dest_url, title,
-            ))),
+                link_type,
+                dest_url,
+                title,
+                ..
+            } => {
+                let mut link = Link::new(dest_url.to_string(), LinkKind::from(link_type));
+                if !title.is_empty() {
+                    link.title = Some(title.to_string());
+                }
+                if self.options.external_links.is_external(&link.target) {
+                    link.attributes = self.options.external_links.attributes();
+                }
+                self.stack
+                    .push(PostComponent::Text(TextComponent::new_styled(Style::Link(
+                        link,
+                    ))))
+            }
             Tag::Image {
                 dest_url, title, ..
             } => self.stack.push(PostComponent::Image {
                 source: dest_url.to_string(),
-                alt: if !title.is_empty() {
+                alt: None,
+                title: if !title.is_empty() {
                     Some(title.to_string())
                 } else {
                     None
                 },
+                figure: None,
+                responsive: None,
             }),
-            Tag::HtmlBlock => todo!(),
+            Tag::HtmlBlock => self.stack.push(PostComponent::Raw(String::new())),
         }
     }
 
@@ -133,7 +328,15 @@ impl<'input> ComponentParser<'input> {
             };
         */
 
+        if matches!(tag, TagEnd::Paragraph) && self.open_admonition(true) {
+            return None;
+        }
+
         match (tag, &mut self.stage) {
+            (TagEnd::MetadataBlock(_), stage) => {
+                *stage = ParseStage::None;
+                None
+            }
             (TagEnd::Item, _)
                 if self
                     .stack
@@ -182,6 +385,15 @@ impl<'input> ComponentParser<'input> {
             (TagEnd::TableHead | TagEnd::TableRow | TagEnd::TableCell, _) => {
                 panic!("expected a table parse stage during table element tags");
             }
+            (TagEnd::Heading(_), _) => {
+                let mut heading = self.stack.pop()?;
+                self.heading_id(&mut heading);
+                Some(heading)
+            }
+            (TagEnd::Paragraph, _) => {
+                let paragraph = self.stack.pop()?;
+                Some(self.standalone_image(paragraph))
+            }
             _ => self.stack.pop(),
         }
     }
@@ -205,13 +417,23 @@ impl<'input> Iterator for ComponentParser<'input> {
 
     fn next(&mut self) -> Option<Self::Item> {
         Some(loop {
-            let current = self.inner.next()?;
+            let (current, range) = self.inner.next()?;
+            if let Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) = &current {
+                self.link_ranges.push((dest_url.to_string(), range));
+            }
+            if self.image_depth > 0 && self.push_alt(&current) {
+                continue;
+            }
             let result = match current {
                 Event::Start(tag) => {
+                    if matches!(tag, Tag::Image { .. }) {
+                        self.image_depth = 1;
+                    }
                     self.push_cm_start(tag.clone());
                     None
                 }
                 Event::End(tag) => self.push_cm_end(tag.clone()),
+                Event::Text(_) if matches!(self.stage, ParseStage::Metadata) => None,
                 Event::Text(value) => {
                     self.push_text(value);
                     None
@@ -231,12 +453,16 @@ impl<'input> Iterator for ComponentParser<'input> {
                 Event::Html(raw) => Some(PostComponent::Raw(raw.to_string())),
                 Event::InlineHtml(raw) => Some(PostComponent::Raw(raw.to_string())),
                 Event::FootnoteReference(label) => Some(PostComponent::Text(TextComponent {
-                    style: Style::Link(Cow::Owned("#footnote-".to_string() + label.as_ref())),
+                    style: Style::Link(Link::new(
+                        "#footnote-".to_string() + label.as_ref(),
+                        LinkKind::Footnote,
+                    )),
                     content: TextPart::Nested(Box::new(TextComponent {
                         style: Style::Superscript,
                         content: TextPart::Raw(format!("[{}]", label.as_ref())),
                     })),
                 })),
+                Event::SoftBreak if self.open_admonition(false) => None,
                 Event::SoftBreak if self.options.newline_soft_break => {
                     Some(PostComponent::from(TextPart::NewLine))
                 }
@@ -274,3 +500,122 @@ impl<'input> Iterator for ComponentParser<'input> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Vec<PostComponent<'_>> {
+        ComponentParser::new(source).collect()
+    }
+
+    fn figures(source: &str, options: ParserOptions) -> Vec<Option<Figure>> {
+        ComponentParser::with_options(source, options)
+            .filter_map(|it| match it {
+                PostComponent::Image { figure, .. } => Some(figure),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn admonition() {
+        let parsed = parse("> [!NOTE] Custom title\n> Body\n");
+        let [PostComponent::Admonition(admonition)] = parsed.as_slice() else {
+            panic!("expected an admonition, got {:?}", parsed);
+        };
+        assert_eq!(admonition.kind, "note");
+        assert_eq!(admonition.title.plain_text(), "Custom title");
+        assert_eq!(admonition.content.len(), 1);
+        assert!(matches!(
+            &admonition.content[0],
+            PostComponent::Text(text) if text.plain_text() == "Body"
+        ));
+    }
+
+    #[test]
+    fn admonition_default_title() {
+        let parsed = parse("> [!tip]\n> Body\n");
+        let [PostComponent::Admonition(admonition)] = parsed.as_slice() else {
+            panic!("expected an admonition, got {:?}", parsed);
+        };
+        assert_eq!(admonition.title.plain_text(), "Tip");
+    }
+
+    #[test]
+    fn unknown_admonition() {
+        let parsed = parse("> [!SPOILER] Title\n> Body\n");
+        assert!(matches!(parsed.as_slice(), [PostComponent::BlockQuote(_)]));
+    }
+
+    #[test]
+    fn nested_containers() {
+        let kinds = AdmonitionKind::defaults();
+        let (source, _) = admonition::expand_containers(":::note\n:::tip\nA\n:::\n:::\n", &kinds);
+        let parsed = parse(&source);
+        let [PostComponent::Admonition(outer)] = parsed.as_slice() else {
+            panic!("expected an admonition, got {:?}", parsed);
+        };
+        assert_eq!(outer.kind, "note");
+        let [PostComponent::Admonition(inner)] = outer.content.as_slice() else {
+            panic!("expected a nested admonition, got {:?}", outer.content);
+        };
+        assert_eq!(inner.kind, "tip");
+        assert_eq!(inner.content.len(), 1);
+    }
+
+    #[test]
+    fn image_alt() {
+        let parsed = parse("![An *emphasized* `code`\nalt ![inner](i.png) text](a.png)\n");
+        let [PostComponent::Image { alt, figure, .. }] = parsed.as_slice() else {
+            panic!("expected an image, got {:?}", parsed);
+        };
+        assert_eq!(alt.as_deref(), Some("An emphasized code alt inner text"));
+        assert!(figure.is_none());
+
+        let parsed = parse("![](a.png)\n");
+        assert!(matches!(
+            parsed.as_slice(),
+            [PostComponent::Image { alt: None, .. }]
+        ));
+    }
+
+    #[test]
+    fn figure_numbering() {
+        let source = "![A](a.png \"First\")\n\n![B](b.png)\n\nText ![C](c.png \"Inline\")\n\n![D](d.png \"Second\")\n";
+
+        let numbered = ParserOptions {
+            number_figures: true,
+            figure_label: "Slika".to_string(),
+            ..ParserOptions::default()
+        };
+        let numbered = figures(source, numbered);
+        assert_eq!(numbered.len(), 3);
+        assert_eq!(numbered[0].as_ref().map(|it| it.number), Some(Some(1)));
+        assert!(numbered[1].is_none());
+        assert_eq!(numbered[2].as_ref().map(|it| it.number), Some(Some(2)));
+        assert_eq!(numbered[2].as_ref().map(|it| it.label.as_str()), Some("Slika"));
+
+        let unnumbered = figures(source, ParserOptions::default());
+        assert_eq!(unnumbered[0].as_ref().map(|it| it.number), Some(None));
+    }
+
+    #[test]
+    fn heading_ids() {
+        let source = "# Intro\n## Intro\n# Intro 1\n# Intro\n# ?!\n# ?!\n";
+        let ids: Vec<_> = parse(source)
+            .into_iter()
+            .filter_map(|it| match it {
+                PostComponent::Text(TextComponent {
+                    style: Style::Heading { id, .. },
+                    ..
+                }) => id.map(|it| it.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            ["intro", "intro-1", "intro-1-1", "intro-2", "section", "section-1"]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use super::*;
+
+impl<'a> TextPart<'a> {
+    /// Writes text content without any markup.
+    pub fn write_plain(&self, out: &mut String) {
+        match self {
+            TextPart::Empty => {}
+            TextPart::NewLine => out.push('\n'),
+            TextPart::Raw(content) => out.push_str(content),
+            TextPart::Chained(items) => {
+                for it in items {
+                    it.write_plain(out);
+                }
+            }
+            TextPart::Nested(inner) => inner.write_plain(out),
+        }
+    }
+}
+
+impl<'a> TextComponent<'a> {
+    pub fn write_plain(&self, out: &mut String) {
+        self.content.write_plain(out);
+        if matches!(self.style, Style::Paragraph | Style::Heading { .. }) {
+            out.push_str("\n\n");
+        }
+    }
+
+    pub fn plain_text(&self) -> String {
+        let mut result = String::new();
+        self.content.write_plain(&mut result);
+        result
+    }
+}
+
+impl<'a> PostComponent<'a> {
+    /// Writes text content of the component without any markup. Blocks are
+    /// separated by empty lines.
+    pub fn write_plain(&self, out: &mut String) {
+        match self {
+            PostComponent::Placeholder | PostComponent::HorizonalRule | PostComponent::Raw(_) => {}
+            PostComponent::Text(text) => text.write_plain(out),
+            PostComponent::BlockQuote(items) | PostComponent::Chained(items) => {
+                for it in items {
+                    it.write_plain(out);
+                }
+            }
+            PostComponent::Admonition(admonition) => {
+                admonition.title.write_plain(out);
+                out.push_str("\n\n");
+                for it in &admonition.content {
+                    it.write_plain(out);
+                }
+            }
+            PostComponent::Image {
+                alt, title, figure, ..
+            } => {
+                if let Some(alt) = alt {
+                    out.push_str(alt);
+                }
+                if let (Some(title), Some(_)) = (title, figure) {
+                    out.push_str("\n\n");
+                    out.push_str(title);
+                    out.push_str("\n\n");
+                }
+            }
+            PostComponent::CodeBlock { content, .. } => {
+                out.push_str(content);
+                out.push('\n');
+            }
+            PostComponent::List(list) => {
+                for it in &list.items {
+                    it.write_plain(out);
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            PostComponent::Table(table) => {
+                for row in std::iter::once(&table.headers).chain(&table.rows) {
+                    for cell in row {
+                        cell.write_plain(out);
+                        out.push('\t');
+                    }
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            PostComponent::Footnote { id, text } => {
+                out.push_str(id);
+                out.push_str(": ");
+                text.write_plain(out);
+                out.push_str("\n\n");
+            }
+            PostComponent::Latex(tex) => out.push_str(&tex.source),
+        }
+    }
+}
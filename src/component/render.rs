@@ -1,16 +1,31 @@
 use std::fmt::Result;
 
+use handlebars::html_escape;
+
 use super::*;
 
 impl<'a> Style<'a> {
     pub fn render_content(&self, content: &dyn Component, writer: &mut String) -> std::fmt::Result {
         match self {
             Style::None => content.render(writer),
-            Style::Link(target) => {
-                write!(writer, "<a href=\"{}\">", target)?;
+            Style::Link(link) => {
+                write!(writer, "<a href=\"{}\"", link.target)?;
+                if let Some(title) = &link.title {
+                    write!(writer, " title=\"{}\"", html_escape(title))?;
+                }
+                for (name, value) in &link.attributes {
+                    write!(writer, " {}=\"{}\"", name, html_escape(value))?;
+                }
+                writer.write_char('>')?;
                 content.render(writer)?;
                 writer.write_str("</a>")
             }
+            Style::Heading { id: Some(id), .. } => {
+                let tag = self.tag();
+                write!(writer, "<{} id=\"{}\">", tag, id)?;
+                content.render(writer)?;
+                write!(writer, "</{}>", tag)
+            }
             _ => {
                 let tag = self.tag();
                 write!(writer, "<{}>", tag)?;
@@ -111,31 +126,86 @@ impl<'a> Component for TableComponent<'a> {
     }
 }
 
+impl<'a> ListComponent<'a> {
+    pub fn render_start(&self, writer: &mut String) -> std::fmt::Result {
+        match self.numbered {
+            Some(first) if first > 0 => write!(writer, "<ol start=\"{}\">", first),
+            Some(_) => writer.write_str("<ol>"),
+            None => writer.write_str("<ul>"),
+        }
+    }
+
+    pub fn render_end(&self, writer: &mut String) -> std::fmt::Result {
+        writer.write_str(if self.numbered.is_some() { "</ol>" } else { "</ul>" })
+    }
+}
+
 impl<'a> Component for ListComponent<'a> {
     fn render(&self, writer: &mut String) -> std::fmt::Result {
-        let tag = self
-            .numbered
-            .map(|first| {
-                if first > 0 {
-                    format!("ol start=\"{}\"", first)
-                } else {
-                    "ol".to_string()
-                }
-            })
-            .unwrap_or("ul".to_string());
-        writer.write_char('<')?;
-        writer.write_str(tag.as_str())?;
-        writer.write_char('>')?;
-
+        self.render_start(writer)?;
         for item in &self.items {
             writer.write_str("<li>")?;
             item.render(writer)?;
             writer.write_str("</li>")?;
         }
+        self.render_end(writer)
+    }
+}
+
+impl<'a> AdmonitionComponent<'a> {
+    /// Writes admonition markup preceding its content.
+    pub fn render_start(&self, writer: &mut String) -> std::fmt::Result {
+        write!(
+            writer,
+            "<aside class=\"admonition admonition-{}\" role=\"note\">",
+            self.kind
+        )?;
+        writer.write_str("<p class=\"admonition-title\">")?;
+        if let Some(icon) = &self.icon {
+            writer.write_str("<span class=\"admonition-icon\" aria-hidden=\"true\">")?;
+            writer.write_str(icon)?;
+            writer.write_str("</span>")?;
+        }
+        self.title.render(writer)?;
+        writer.write_str("</p><div class=\"admonition-content\">")
+    }
 
-        writer.write_str("</")?;
-        writer.write_str(if self.numbered.is_some() { "ol" } else { "ul" })?;
-        writer.write_char('>')
+    pub fn render_end(&self, writer: &mut String) -> std::fmt::Result {
+        writer.write_str("</div></aside>")
+    }
+}
+
+impl<'a> Component for AdmonitionComponent<'a> {
+    fn render(&self, writer: &mut String) -> std::fmt::Result {
+        self.render_start(writer)?;
+        for it in &self.content {
+            it.render(writer)?;
+        }
+        self.render_end(writer)
+    }
+}
+
+impl Figure {
+    pub fn render_start(&self, writer: &mut String) -> std::fmt::Result {
+        match self.id() {
+            Some(id) => write!(writer, "<figure id=\"{}\">", id),
+            None => writer.write_str("<figure>"),
+        }
+    }
+
+    /// Writes the caption and closes the figure.
+    pub fn render_end(&self, caption: &str, writer: &mut String) -> std::fmt::Result {
+        writer.write_str("<figcaption>")?;
+        if let Some(number) = self.number {
+            write!(
+                writer,
+                "<span class=\"figure-number\">{} {}:</span> ",
+                html_escape(&self.label),
+                number
+            )?;
+        }
+        writer.write_str(&html_escape(caption))?;
+        writer.write_str("</figcaption></figure>")
     }
 }
 
@@ -151,16 +221,49 @@ impl<'a> Component for PostComponent<'a> {
                 }
                 writer.write_str("</blockquote>")
             }
-            PostComponent::Image { source, alt } => {
+            PostComponent::Admonition(it) => it.render(writer),
+            PostComponent::Image {
+                source,
+                alt,
+                title,
+                figure,
+                responsive,
+            } => {
+                if let Some(figure) = figure {
+                    figure.render_start(writer)?;
+                }
+                let sources = responsive.as_ref().map(|it| &it.sources[..]).unwrap_or_default();
+                if !sources.is_empty() {
+                    writer.write_str("<picture>")?;
+                }
+                for it in sources {
+                    write!(writer, "<source type=\"{}\" srcset=\"{}\"", it.kind, it.srcset)?;
+                    if let Some(responsive) = responsive {
+                        write!(writer, " sizes=\"{}\"", responsive.sizes)?;
+                    }
+                    writer.write_str("/>")?;
+                }
+
                 writer.write_str("<img src=\"")?;
                 writer.write_str(source)?;
+                writer.write_str("\" alt=\"")?;
+                writer.write_str(&html_escape(alt.as_deref().unwrap_or_default()))?;
                 writer.write_str("\"")?;
-                if let Some(alt) = alt {
-                    writer.write_str(" alt=\"")?;
-                    writer.write_str(alt)?;
-                    writer.write_str("\"")?;
+                if let (Some(title), None) = (title, figure) {
+                    write!(writer, " title=\"{}\"", html_escape(title))?;
                 }
-                writer.write_str("/>")
+                if let Some(responsive) = responsive {
+                    write!(writer, " width=\"{}\" height=\"{}\"", responsive.width, responsive.height)?;
+                }
+                writer.write_str(" loading=\"lazy\" decoding=\"async\"/>")?;
+
+                if !sources.is_empty() {
+                    writer.write_str("</picture>")?;
+                }
+                if let Some(figure) = figure {
+                    figure.render_end(title.as_deref().unwrap_or_default(), writer)?;
+                }
+                Ok(())
             }
             PostComponent::CodeBlock { language, content } => {
                 writer.write_str("<pre><code class=\"block")?;
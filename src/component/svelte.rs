@@ -0,0 +1,293 @@
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use handlebars::html_escape;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Svelte components used in place of HTML for some Markdown constructs.
+///
+/// Values are import paths (e.g. `$lib/Image.svelte`); component name is
+/// derived from the file name without extension.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SvelteOptions {
+    /// Receives `src` and `alt` props.
+    pub image: Option<String>,
+    /// Receives `language` and `code` props.
+    pub code_block: Option<String>,
+    /// Receives `kind`, `title` (HTML) and `icon` props, with admonition
+    /// content in the default slot.
+    pub admonition: Option<String>,
+}
+
+/// Returns component name for an import path.
+///
+/// File name is converted to a PascalCase identifier, e.g. `code-block.svelte`
+/// becomes `CodeBlock`.
+fn component_name(import: &str) -> String {
+    let stem = Path::new(import)
+        .file_stem()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name: String = stem
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_ascii_uppercase()).into_iter().chain(chars)
+        })
+        .collect();
+    match name.chars().next() {
+        Some(first) if !first.is_ascii_digit() => name,
+        _ => format!("Component{}", name),
+    }
+}
+
+/// Writes `text` escaping braces so Svelte doesn't treat them as
+/// expressions.
+fn escape_braces(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '{' => out.push_str("&#123;"),
+            '}' => out.push_str("&#125;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Elements whose content isn't decoded, so braces in them can't be escaped
+/// with character references.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Returns byte range of the first `<script>` or `<style>` element in `html`,
+/// up to the end of `html` if the element isn't closed.
+fn raw_text_element(html: &str) -> Option<(usize, usize)> {
+    let lower = html.to_ascii_lowercase();
+    RAW_TEXT_ELEMENTS
+        .iter()
+        .filter_map(|name| {
+            let open = format!("<{}", name);
+            let close = format!("</{}>", name);
+            lower
+                .match_indices(&open)
+                .map(|(start, _)| start)
+                .find(|start| {
+                    matches!(lower[start + open.len()..].chars().next(), Some(c) if c == '>' || c == '/' || c.is_ascii_whitespace())
+                })
+                .map(|start| {
+                    let end = lower[start..]
+                        .find(&close)
+                        .map(|it| start + it + close.len())
+                        .unwrap_or(html.len());
+                    (start, end)
+                })
+        })
+        .min()
+}
+
+/// Writes rendered HTML as Svelte markup.
+///
+/// Braces are escaped as character references, except in `<script>` and
+/// `<style>` elements where those aren't decoded; such elements are emitted
+/// with `{@html}`.
+fn escape_markup(html: &str, out: &mut String) {
+    let mut rest = html;
+    while let Some((start, end)) = raw_text_element(rest) {
+        escape_braces(&rest[..start], out);
+        out.push_str("{@html ");
+        out.push_str(&js_string(&rest[start..end]));
+        out.push('}');
+        rest = &rest[end..];
+    }
+    escape_braces(rest, out);
+}
+
+/// Returns `text` as JavaScript string literal.
+fn js_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+fn attribute(name: &str, value: &str, out: &mut String) {
+    out.push(' ');
+    out.push_str(name);
+    out.push_str("=\"");
+    escape_braces(&html_escape(value), out);
+    out.push('"');
+}
+
+/// Renders components as Svelte markup, mapping some of them to Svelte
+/// components.
+pub struct SvelteRenderer<'o> {
+    options: &'o SvelteOptions,
+    /// Component name -> import path of used components.
+    imports: BTreeMap<String, String>,
+}
+
+impl<'o> SvelteRenderer<'o> {
+    pub fn new(options: &'o SvelteOptions) -> Self {
+        SvelteRenderer {
+            options,
+            imports: BTreeMap::new(),
+        }
+    }
+
+    /// Components that need to be imported by the rendered markup.
+    pub fn imports(&self) -> &BTreeMap<String, String> {
+        &self.imports
+    }
+
+    /// Returns the name `path` is imported as, numbering components whose
+    /// file names match one that's already imported.
+    fn import(&mut self, path: &str) -> String {
+        if let Some((name, _)) = self.imports.iter().find(|(_, it)| *it == path) {
+            return name.clone();
+        }
+        let base = component_name(path);
+        let mut name = base.clone();
+        let mut index = 1;
+        while self.imports.contains_key(&name) {
+            index += 1;
+            name = format!("{}{}", base, index);
+        }
+        self.imports.insert(name.clone(), path.to_string());
+        name
+    }
+
+    pub fn render(&mut self, component: &PostComponent, out: &mut String) -> std::fmt::Result {
+        match component {
+            PostComponent::Image {
+                source,
+                alt,
+                title,
+                figure,
+                responsive,
+            } if self.options.image.is_some() => {
+                let name = self.import(self.options.image.as_deref().unwrap_or_default());
+                let mut caption = String::new();
+                if let Some(figure) = figure {
+                    figure.render_start(out)?;
+                    figure.render_end(title.as_deref().unwrap_or_default(), &mut caption)?;
+                }
+                write!(out, "<{}", name)?;
+                attribute("src", source, out);
+                attribute("alt", alt.as_deref().unwrap_or_default(), out);
+                if let (Some(title), None) = (title, figure) {
+                    attribute("title", title, out);
+                }
+                if let Some(responsive) = responsive {
+                    write!(out, " width={{{}}} height={{{}}}", responsive.width, responsive.height)?;
+                    attribute("sizes", &responsive.sizes, out);
+                    let sources = serde_json::to_string(&responsive.sources).unwrap_or_default();
+                    write!(out, " sources={{{}}}", sources)?;
+                }
+                out.write_str(" />")?;
+                escape_braces(&caption, out);
+                Ok(())
+            }
+            PostComponent::CodeBlock { language, content } if self.options.code_block.is_some() => {
+                let name = self.import(self.options.code_block.as_deref().unwrap_or_default());
+                write!(out, "<{}", name)?;
+                if let Some(language) = language {
+                    attribute("language", language, out);
+                }
+                write!(out, " code={{{}}} />", js_string(content))
+            }
+            PostComponent::Admonition(admonition) if self.options.admonition.is_some() => {
+                let name = self.import(self.options.admonition.as_deref().unwrap_or_default());
+                let mut title = String::new();
+                admonition.title.render(&mut title)?;
+
+                write!(out, "<{}", name)?;
+                attribute("kind", &admonition.kind, out);
+                write!(out, " title={{{}}}", js_string(&title))?;
+                if let Some(icon) = &admonition.icon {
+                    attribute("icon", icon, out);
+                }
+                out.write_char('>')?;
+                for it in &admonition.content {
+                    self.render(it, out)?;
+                }
+                write!(out, "</{}>", name)
+            }
+            PostComponent::Admonition(admonition) => {
+                let mut html = String::new();
+                admonition.render_start(&mut html)?;
+                escape_markup(&html, out);
+                for it in &admonition.content {
+                    self.render(it, out)?;
+                }
+                admonition.render_end(out)
+            }
+            PostComponent::BlockQuote(content) => {
+                out.write_str("<blockquote>")?;
+                for it in content {
+                    self.render(it, out)?;
+                }
+                out.write_str("</blockquote>")
+            }
+            PostComponent::List(list) => {
+                list.render_start(out)?;
+                for item in &list.items {
+                    out.write_str("<li>")?;
+                    self.render(item, out)?;
+                    out.write_str("</li>")?;
+                }
+                list.render_end(out)
+            }
+            PostComponent::Chained(items) => {
+                for item in items {
+                    self.render(item, out)?;
+                }
+                Ok(())
+            }
+            PostComponent::Raw(raw) => {
+                escape_markup(raw, out);
+                Ok(())
+            }
+            other => {
+                let mut html = String::with_capacity(256);
+                other.render(&mut html)?;
+                escape_markup(&html, out);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn component_names() {
+        assert_eq!(component_name("$lib/Image.svelte"), "Image");
+        assert_eq!(component_name("$lib/code-block.svelte"), "CodeBlock");
+        assert_eq!(component_name("$lib/2col.svelte"), "Component2col");
+    }
+
+    #[test]
+    fn imports_with_same_file_name() {
+        let options = SvelteOptions::default();
+        let mut renderer = SvelteRenderer::new(&options);
+        assert_eq!(renderer.import("$lib/a/Image.svelte"), "Image");
+        assert_eq!(renderer.import("$lib/b/Image.svelte"), "Image2");
+        assert_eq!(renderer.import("$lib/a/Image.svelte"), "Image");
+        assert_eq!(renderer.imports().len(), 2);
+    }
+
+    #[test]
+    fn raw_html_braces() {
+        let options = SvelteOptions::default();
+        let mut renderer = SvelteRenderer::new(&options);
+        let raw = PostComponent::Raw(
+            "<div data-x='{\"a\": 1}'></div><script type=\"module\">let a = {};</script>"
+                .to_string(),
+        );
+        let mut out = String::new();
+        renderer.render(&raw, &mut out).unwrap();
+        assert_eq!(
+            out,
+            r#"<div data-x='&#123;"a": 1&#125;'></div>{@html "<script type=\"module\">let a = {};</script>"}"#
+        );
+    }
+}
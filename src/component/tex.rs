@@ -1,18 +1,32 @@
 use std::borrow::Cow;
 
-#[derive(Debug, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Format {
     Inline,
     #[default]
     Multiline,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LatexRenderInfo {}
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LatexComponent<'a> {
     pub format: Format,
     pub source: Cow<'a, str>,
+    #[serde(skip)]
     pub rendered: Option<LatexRenderInfo>,
 }
+
+impl<'a> LatexComponent<'a> {
+    pub fn into_owned(self) -> LatexComponent<'static> {
+        LatexComponent {
+            format: self.format,
+            source: Cow::Owned(self.source.into_owned()),
+            rendered: self.rendered,
+        }
+    }
+}
@@ -1,17 +1,24 @@
-use std::{borrow::Cow, fmt::Write};
+use std::{borrow::Cow, collections::BTreeMap, fmt::Write};
+
+use serde::{Deserialize, Serialize};
 
 use super::{Component, PostEntry, Structured};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum Style<'a> {
     #[default]
     None,
     Span,
     Paragraph,
-    Heading(u8),
+    Heading {
+        level: u8,
+        /// Anchor of the heading, unique within a post.
+        id: Option<Cow<'a, str>>,
+    },
     Emphasis,
     Strong,
-    Link(Cow<'a, str>),
+    Link(Link<'a>),
     Code,
     Strikethrough,
     Highlight,
@@ -20,12 +27,33 @@ pub enum Style<'a> {
 }
 
 impl<'a> Style<'a> {
+    /// Returns the style without borrowed data.
+    pub fn into_owned(self) -> Style<'static> {
+        match self {
+            Style::None => Style::None,
+            Style::Span => Style::Span,
+            Style::Paragraph => Style::Paragraph,
+            Style::Heading { level, id } => Style::Heading {
+                level,
+                id: id.map(|it| Cow::Owned(it.into_owned())),
+            },
+            Style::Emphasis => Style::Emphasis,
+            Style::Strong => Style::Strong,
+            Style::Link(link) => Style::Link(link.into_owned()),
+            Style::Code => Style::Code,
+            Style::Strikethrough => Style::Strikethrough,
+            Style::Highlight => Style::Highlight,
+            Style::Superscript => Style::Superscript,
+            Style::Subscript => Style::Subscript,
+        }
+    }
+
     pub fn tag(&self) -> &'static str {
         match self {
             Style::None => "span",
             Style::Span => "span",
             Style::Paragraph => "p",
-            Style::Heading(level) => match level {
+            Style::Heading { level, .. } => match level {
                 1 => "h1",
                 2 => "h2",
                 3 => "h3",
@@ -47,9 +75,9 @@ impl<'a> Style<'a> {
 
     pub fn copy(&self) -> Option<Self> {
         match self {
-            Style::Link(_) => None,
+            Style::Link(_) | Style::Heading { .. } => None,
             _ => unsafe {
-                // SAFETY: All variants except `Link` are copyable.
+                // SAFETY: All variants except `Link` and `Heading` are copyable.
                 let mut result = Self::default();
                 std::ptr::copy_nonoverlapping(self, &mut result, 1);
                 Some(result)
@@ -58,16 +86,89 @@ impl<'a> Style<'a> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[text](url)`
+    #[default]
+    Inline,
+    /// `[text][label]`
+    Reference,
+    /// `[label][]`
+    Collapsed,
+    /// `[label]`
+    Shortcut,
+    /// `<https://example.com>`
+    Autolink,
+    /// `<user@example.com>`
+    Email,
+    /// Reference to a footnote.
+    Footnote,
+}
+
+impl From<pulldown_cmark::LinkType> for LinkKind {
+    fn from(value: pulldown_cmark::LinkType) -> Self {
+        use pulldown_cmark::LinkType;
+        match value {
+            LinkType::Inline => LinkKind::Inline,
+            LinkType::Reference | LinkType::ReferenceUnknown => LinkKind::Reference,
+            LinkType::Collapsed | LinkType::CollapsedUnknown => LinkKind::Collapsed,
+            LinkType::Shortcut | LinkType::ShortcutUnknown => LinkKind::Shortcut,
+            LinkType::Autolink => LinkKind::Autolink,
+            LinkType::Email => LinkKind::Email,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link<'a> {
+    pub target: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub kind: LinkKind,
+    /// Additional HTML attributes, e.g. `rel` of external links.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl<'a> Link<'a> {
+    pub fn new(target: impl Into<Cow<'a, str>>, kind: LinkKind) -> Self {
+        let mut target = target.into();
+        if kind == LinkKind::Email && !target.starts_with("mailto:") {
+            target = Cow::Owned(format!("mailto:{}", target));
+        }
+        Link {
+            target,
+            title: None,
+            kind,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    pub fn into_owned(self) -> Link<'static> {
+        Link {
+            target: Cow::Owned(self.target.into_owned()),
+            title: self.title,
+            kind: self.kind,
+            attributes: self.attributes,
+        }
+    }
+}
+
 impl From<pulldown_cmark::HeadingLevel> for Style<'_> {
     fn from(level: pulldown_cmark::HeadingLevel) -> Self {
-        Style::Heading(match level {
-            pulldown_cmark::HeadingLevel::H1 => 1,
-            pulldown_cmark::HeadingLevel::H2 => 2,
-            pulldown_cmark::HeadingLevel::H3 => 3,
-            pulldown_cmark::HeadingLevel::H4 => 4,
-            pulldown_cmark::HeadingLevel::H5 => 5,
-            pulldown_cmark::HeadingLevel::H6 => 6,
-        })
+        Style::Heading {
+            level: match level {
+                pulldown_cmark::HeadingLevel::H1 => 1,
+                pulldown_cmark::HeadingLevel::H2 => 2,
+                pulldown_cmark::HeadingLevel::H3 => 3,
+                pulldown_cmark::HeadingLevel::H4 => 4,
+                pulldown_cmark::HeadingLevel::H5 => 5,
+                pulldown_cmark::HeadingLevel::H6 => 6,
+            },
+            id: None,
+        }
     }
 }
 
@@ -85,7 +186,8 @@ pub struct TextSegment<'a> {
     pub content: SegmentContent<'a>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum TextPart<'a> {
     #[default]
     Empty,
@@ -96,6 +198,18 @@ pub enum TextPart<'a> {
 }
 
 impl<'a> TextPart<'a> {
+    pub fn into_owned(self) -> TextPart<'static> {
+        match self {
+            TextPart::Empty => TextPart::Empty,
+            TextPart::NewLine => TextPart::NewLine,
+            TextPart::Raw(it) => TextPart::Raw(it),
+            TextPart::Chained(items) => {
+                TextPart::Chained(items.into_iter().map(TextPart::into_owned).collect())
+            }
+            TextPart::Nested(text) => TextPart::Nested(Box::new(text.into_owned())),
+        }
+    }
+
     pub fn wrapper_ref(&self) -> Option<&Style> {
         match self {
             TextPart::Nested(text) => Some(&text.style),
@@ -192,7 +306,7 @@ impl<'s> From<TextComponent<'s>> for TextPart<'s> {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TextComponent<'a> {
     pub style: Style<'a>,
     pub content: TextPart<'a>,
@@ -204,6 +318,13 @@ impl<'a> TextComponent<'a> {
         content: TextPart::Empty,
     };
 
+    pub fn into_owned(self) -> TextComponent<'static> {
+        TextComponent {
+            style: self.style.into_owned(),
+            content: self.content.into_owned(),
+        }
+    }
+
     #[inline]
     pub fn new(content: impl ToString) -> Self {
         TextComponent {
@@ -223,7 +344,7 @@ impl<'a> TextComponent<'a> {
     #[inline]
     pub fn new_link(target: impl ToString, content: impl ToString) -> Self {
         TextComponent {
-            style: Style::Link(Cow::Owned(target.to_string())),
+            style: Style::Link(Link::new(target.to_string(), LinkKind::Inline)),
             content: TextPart::Raw(content.to_string()),
         }
     }
@@ -293,3 +414,21 @@ impl<'a> From<TextComponent<'a>> for TextValue<'a> {
         TextValue::Segment(TextPart::Nested(Box::new(value)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_links() {
+        let link = Link::new("me@example.com", LinkKind::Email);
+        assert_eq!(link.target, "mailto:me@example.com");
+
+        let link = Link::new("mailto:me@example.com", LinkKind::Email);
+        assert_eq!(link.target, "mailto:me@example.com");
+
+        // only autolinked emails are prefixed
+        let link = Link::new("me@example.com", LinkKind::Inline);
+        assert_eq!(link.target, "me@example.com");
+    }
+}
@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlKind {
+    Image,
+    Link,
+}
+
+/// Called with every URL in a component tree; returning a value replaces the
+/// URL.
+pub type UrlVisitor<'f> = dyn FnMut(UrlKind, &str) -> Option<String> + 'f;
+
+/// Called with source and responsive image data of every image in a component
+/// tree.
+pub type ImageVisitor<'f> = dyn FnMut(&str, &mut Option<ResponsiveImage>) + 'f;
+
+impl<'a> TextComponent<'a> {
+    pub fn visit_urls(&mut self, f: &mut UrlVisitor) {
+        if let Style::Link(link) = &mut self.style {
+            if let Some(it) = f(UrlKind::Link, &link.target) {
+                link.target = Cow::Owned(it);
+            }
+        }
+        self.content.visit_urls(f);
+    }
+}
+
+impl<'a> TextPart<'a> {
+    pub fn visit_urls(&mut self, f: &mut UrlVisitor) {
+        match self {
+            TextPart::Chained(items) => {
+                for it in items {
+                    it.visit_urls(f);
+                }
+            }
+            TextPart::Nested(inner) => inner.visit_urls(f),
+            TextPart::Empty | TextPart::NewLine | TextPart::Raw(_) => {}
+        }
+    }
+}
+
+impl<'a> PostComponent<'a> {
+    /// Visits image sources and link targets in the component tree.
+    pub fn visit_urls(&mut self, f: &mut UrlVisitor) {
+        match self {
+            PostComponent::Text(text) | PostComponent::Footnote { text, .. } => text.visit_urls(f),
+            PostComponent::BlockQuote(items) | PostComponent::Chained(items) => {
+                for it in items {
+                    it.visit_urls(f);
+                }
+            }
+            PostComponent::Admonition(admonition) => {
+                admonition.title.visit_urls(f);
+                for it in &mut admonition.content {
+                    it.visit_urls(f);
+                }
+            }
+            PostComponent::Image { source, .. } => {
+                if let Some(it) = f(UrlKind::Image, source) {
+                    *source = it;
+                }
+            }
+            PostComponent::List(list) => {
+                for it in &mut list.items {
+                    it.visit_urls(f);
+                }
+            }
+            PostComponent::Table(table) => {
+                for it in table.headers.iter_mut().chain(table.rows.iter_mut().flatten()) {
+                    it.visit_urls(f);
+                }
+            }
+            PostComponent::Placeholder
+            | PostComponent::CodeBlock { .. }
+            | PostComponent::HorizonalRule
+            | PostComponent::Latex(_)
+            | PostComponent::Raw(_) => {}
+        }
+    }
+
+    /// Visits images in the component tree.
+    pub fn visit_images(&mut self, f: &mut ImageVisitor) {
+        match self {
+            PostComponent::Image {
+                source, responsive, ..
+            } => f(source, responsive),
+            PostComponent::BlockQuote(items) | PostComponent::Chained(items) => {
+                for it in items {
+                    it.visit_images(f);
+                }
+            }
+            PostComponent::Admonition(admonition) => {
+                for it in &mut admonition.content {
+                    it.visit_images(f);
+                }
+            }
+            PostComponent::List(list) => {
+                for it in &mut list.items {
+                    it.visit_images(f);
+                }
+            }
+            PostComponent::Table(table) => {
+                for it in table.headers.iter_mut().chain(table.rows.iter_mut().flatten()) {
+                    it.visit_images(f);
+                }
+            }
+            PostComponent::Placeholder
+            | PostComponent::Text(_)
+            | PostComponent::Footnote { .. }
+            | PostComponent::CodeBlock { .. }
+            | PostComponent::HorizonalRule
+            | PostComponent::Latex(_)
+            | PostComponent::Raw(_) => {}
+        }
+    }
+
+    /// Calls `f` with ids of elements that can be linked to with a URL
+    /// fragment: headings, numbered figures and footnotes.
+    pub fn visit_anchors(&self, f: &mut dyn FnMut(&str)) {
+        match self {
+            PostComponent::Text(TextComponent {
+                style: Style::Heading { id: Some(id), .. },
+                ..
+            }) => f(id),
+            PostComponent::Image {
+                figure: Some(figure),
+                ..
+            } => {
+                if let Some(id) = figure.id() {
+                    f(&id);
+                }
+            }
+            PostComponent::Footnote { id, .. } => f(&format!("footnote-{}", id)),
+            PostComponent::BlockQuote(items) | PostComponent::Chained(items) => {
+                for it in items {
+                    it.visit_anchors(f);
+                }
+            }
+            PostComponent::Admonition(admonition) => {
+                for it in &admonition.content {
+                    it.visit_anchors(f);
+                }
+            }
+            PostComponent::List(list) => {
+                for it in &list.items {
+                    it.visit_anchors(f);
+                }
+            }
+            PostComponent::Table(table) => {
+                for it in table.cells() {
+                    it.visit_anchors(f);
+                }
+            }
+            PostComponent::Placeholder
+            | PostComponent::Text(_)
+            | PostComponent::Image { .. }
+            | PostComponent::CodeBlock { .. }
+            | PostComponent::HorizonalRule
+            | PostComponent::Latex(_)
+            | PostComponent::Raw(_) => {}
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{parser::ParserOptions, svelte::SvelteOptions},
+    error::FormatError,
+    excerpt::ExcerptOptions,
+    images::ImageOptions,
+    links,
+    permalink,
+    post::PostInfo,
+    redirect::RedirectOptions,
+    schema::FrontmatterSchema,
+};
+
+/// Name of the configuration file expected in the blog root.
+pub const CONFIG_FILE: &str = "blog.yaml";
+
+/// Layout template used when none is configured.
+pub const DEFAULT_LAYOUT: &str = "layout";
+/// Layout name that disables layouts, so only the article is written.
+pub const NO_LAYOUT: &str = "none";
+
+/// Site wide information used in page layouts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteConfig {
+    pub title: Option<String>,
+    /// Used for posts without a description.
+    pub description: Option<String>,
+    /// Base URL the site is deployed at, e.g. `https://example.com`. Needed
+    /// for absolute canonical links.
+    pub url: Option<String>,
+    /// Language of the pages, defaults to `en`.
+    pub lang: Option<String>,
+    /// Stylesheet URLs linked from every page.
+    pub stylesheets: Vec<String>,
+    /// URL of the RSS, Atom or JSON feed advertised for autodiscovery.
+    pub feed: Option<String>,
+    /// Twitter handle of the site, e.g. `@example`.
+    pub twitter: Option<String>,
+    /// Image used in link previews of posts without a cover image.
+    pub image: Option<String>,
+}
+
+impl SiteConfig {
+    /// Returns absolute URL for a root relative `url` if base URL is
+    /// configured.
+    pub fn absolute_url(&self, url: &str) -> Option<String> {
+        self.url
+            .as_ref()
+            .map(|base| format!("{}/{}", base.trim_end_matches('/'), url.trim_start_matches('/')))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlogConfig {
+    /// Site information used in page layouts.
+    pub site: SiteConfig,
+    /// Default layout template posts are rendered into. Set to `none` to
+    /// write only the article, e.g. for inclusion in another app.
+    pub layout: Option<String>,
+
+    /// IANA timezone in which post dates without an offset are interpreted.
+    /// Defaults to UTC.
+    pub timezone: Option<Tz>,
+
+    /// Output path pattern, e.g. `/:year/:month/:slug/`. See
+    /// [`permalink::Permalink::resolve`] for supported placeholders.
+    pub permalink: Option<String>,
+    /// Write posts without an explicit extension in the permalink to
+    /// `<permalink>/index.<ext>`.
+    pub pretty_urls: bool,
+    /// URL pattern of tag pages used by the `url_for` template helper, e.g.
+    /// `/tags/:tag/`.
+    pub tag_url: Option<String>,
+
+    /// Redirects generated for post aliases.
+    pub redirects: RedirectOptions,
+
+    /// Options used when converting Markdown sources into components.
+    pub markdown: ParserOptions,
+    /// Limits of excerpts generated for posts without a `<!-- more -->`
+    /// marker.
+    pub excerpt: ExcerptOptions,
+    /// Svelte components used by `svelte` output format.
+    pub svelte: SvelteOptions,
+    /// Resized variants generated for local images.
+    pub images: ImageOptions,
+
+    /// Custom frontmatter fields, loaded from a separate schema file.
+    #[serde(skip)]
+    pub schema: Option<FrontmatterSchema>,
+}
+
+impl BlogConfig {
+    /// Loads configuration from `blog.yaml` in the provided blog root, or
+    /// returns the default configuration if the file doesn't exist.
+    pub fn load(root: impl AsRef<Path>) -> Result<BlogConfig, FormatError> {
+        let root = root.as_ref();
+        let path = root.join(CONFIG_FILE);
+
+        let mut config: BlogConfig = if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            serde_yaml::from_reader(reader).map_err(|err| FormatError::yaml(&path, 0, err))?
+        } else {
+            BlogConfig::default()
+        };
+        config.schema = FrontmatterSchema::load(root)?;
+        if let Some(url) = &config.site.url {
+            config.markdown.external_links.site_host = links::host(url).map(str::to_string);
+        }
+
+        Ok(config)
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or(Tz::UTC)
+    }
+
+    /// Returns layout template name for a post, or `None` if the post
+    /// should be written without a layout.
+    pub fn layout<'a>(&'a self, info: &'a PostInfo) -> Option<&'a str> {
+        let layout = info
+            .layout
+            .as_deref()
+            .or(self.layout.as_deref())
+            .unwrap_or(DEFAULT_LAYOUT);
+        if layout == NO_LAYOUT {
+            None
+        } else {
+            Some(layout)
+        }
+    }
+
+    pub fn permalink(&self) -> &str {
+        self.permalink.as_deref().unwrap_or(permalink::DEFAULT_PATTERN)
+    }
+}
@@ -0,0 +1,224 @@
+use chrono::{
+    DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::FormatError;
+
+/// Formats accepted for local times, interpreted in post or blog timezone.
+const LOCAL_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parses a date in RFC 3339 format, a plain date (`2023-04-02`) or a local
+/// time (`2023-04-02 19:55`).
+///
+/// Plain dates and local times are interpreted in the provided timezone.
+pub fn parse_date(value: &str, tz: Tz) -> Result<DateTime<FixedOffset>, FormatError> {
+    parse_date_checked(value, tz).map(|(time, _)| time)
+}
+
+/// Same as [`parse_date`], but also returns whether `value` is a local time
+/// skipped by a DST transition.
+///
+/// Skipped times are shifted forward by the length of the gap, using the
+/// offset from before the transition.
+pub fn parse_date_checked(
+    value: &str,
+    tz: Tz,
+) -> Result<(DateTime<FixedOffset>, bool), FormatError> {
+    let value = value.trim();
+
+    let err = match DateTime::parse_from_rfc3339(value) {
+        Ok(it) => return Ok((it, false)),
+        Err(err) => err,
+    };
+
+    let local = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|it| it.and_hms_opt(0, 0, 0))
+        });
+
+    let local = match local {
+        Some(it) => it,
+        None => {
+            return Err(FormatError::DateTimeParse {
+                invalid: value.to_string(),
+                source: err,
+            })
+        }
+    };
+
+    let (time, skipped) = match tz.from_local_datetime(&local).earliest() {
+        Some(it) => (it, false),
+        None => {
+            let before = tz.offset_from_local_datetime(&(local - Duration::days(1)));
+            let offset = before.earliest().map(|it| it.fix()).unwrap_or(Utc.fix());
+            (tz.from_utc_datetime(&(local - offset)), true)
+        }
+    };
+
+    Ok((time.with_timezone(&time.offset().fix()), skipped))
+}
+
+/// Date specified in frontmatter.
+///
+/// Values without an explicit offset can only be resolved once the timezone
+/// is known, so they're stored as is until [`PostDate::resolve`] is called.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum PostDate {
+    Resolved(DateTime<FixedOffset>),
+    Raw(String),
+}
+
+impl<'de> Deserialize<'de> for PostDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Dates are strings, except in TOML which has native datetimes.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            String(String),
+            Toml(toml::value::Datetime),
+        }
+
+        let value = match Value::deserialize(deserializer)? {
+            Value::String(it) => it,
+            Value::Toml(it) => it.to_string(),
+        };
+        Ok(match value.parse::<DateTime<FixedOffset>>() {
+            Ok(it) => PostDate::Resolved(it),
+            Err(_) => PostDate::Raw(value),
+        })
+    }
+}
+
+impl PostDate {
+    /// Interprets a raw value in `tz`. Returns true if the value is a local
+    /// time skipped by a DST transition, see [`parse_date_checked`].
+    pub fn resolve(&mut self, tz: Tz) -> Result<bool, FormatError> {
+        if let PostDate::Raw(value) = self {
+            let (time, skipped) = parse_date_checked(value, tz)?;
+            *self = PostDate::Resolved(time);
+            return Ok(skipped);
+        }
+        Ok(false)
+    }
+
+    /// Returns the date in UTC, or `None` if it wasn't resolved yet.
+    pub fn utc(&self) -> Option<DateTime<Utc>> {
+        match self {
+            PostDate::Resolved(it) => Some(it.with_timezone(&Utc)),
+            PostDate::Raw(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str, tz: &str) -> (String, bool) {
+        let (time, skipped) = parse_date_checked(value, tz.parse().unwrap()).unwrap();
+        (time.to_rfc3339(), skipped)
+    }
+
+    #[test]
+    fn offsets() {
+        let (time, _) = parse("2023-04-02T19:55:00+05:30", "Europe/Berlin");
+        assert_eq!(time, "2023-04-02T19:55:00+05:30");
+    }
+
+    #[test]
+    fn local_times() {
+        assert_eq!(
+            parse("2023-04-02 19:55", "Europe/Berlin"),
+            ("2023-04-02T19:55:00+02:00".to_string(), false)
+        );
+        assert_eq!(
+            parse("2023-01-02T19:55:10", "Europe/Berlin"),
+            ("2023-01-02T19:55:10+01:00".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(
+            parse("2023-04-02", "America/New_York"),
+            ("2023-04-02T00:00:00-04:00".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn ambiguous_times() {
+        // clocks go back from 3:00 to 2:00, earlier time is used
+        assert_eq!(
+            parse("2023-10-29 02:30", "Europe/Berlin"),
+            ("2023-10-29T02:30:00+02:00".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn skipped_times() {
+        // clocks go forward from 2:00 to 3:00
+        assert_eq!(
+            parse("2023-03-26 02:30", "Europe/Berlin"),
+            ("2023-03-26T03:30:00+02:00".to_string(), true)
+        );
+        assert_eq!(
+            parse("2023-03-12 02:15", "America/New_York"),
+            ("2023-03-12T03:15:00-04:00".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse_date("yesterday", Tz::UTC).is_err());
+        assert!(parse_date("2023-02-30", Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn toml_dates() {
+        #[derive(Deserialize)]
+        struct Info {
+            date: PostDate,
+        }
+
+        let date = |source: &str| toml::from_str::<Info>(source).unwrap().date;
+
+        assert!(matches!(
+            date("date = 2023-04-02T19:55:00Z"),
+            PostDate::Resolved(it) if it.to_rfc3339() == "2023-04-02T19:55:00+00:00"
+        ));
+        assert!(matches!(
+            date("date = 2023-04-02T19:55:00"),
+            PostDate::Raw(it) if it == "2023-04-02T19:55:00"
+        ));
+        assert!(matches!(date("date = 2023-04-02"), PostDate::Raw(it) if it == "2023-04-02"));
+        assert!(matches!(date("date = \"2023-04-02\""), PostDate::Raw(it) if it == "2023-04-02"));
+    }
+
+    #[test]
+    fn resolve() {
+        let mut date = PostDate::Raw("2023-03-26 02:30".to_string());
+        assert!(date.resolve("Europe/Berlin".parse().unwrap()).unwrap());
+        assert_eq!(
+            date.utc().unwrap().to_rfc3339(),
+            "2023-03-26T01:30:00+00:00"
+        );
+        // resolved dates are left as they are
+        assert!(!date.resolve(Tz::UTC).unwrap());
+        assert_eq!(
+            date.utc().unwrap().to_rfc3339(),
+            "2023-03-26T01:30:00+00:00"
+        );
+    }
+}
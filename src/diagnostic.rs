@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+
+use console::style;
+use serde::Serialize;
+
+use crate::{
+    arguments::{Args, MessageFormat},
+    error::{BlogError, FormatWarning, SourceLocation},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Range of a source file a diagnostic points to; lines and columns are
+/// 1-based and the end is inclusive.
+#[derive(Debug, Clone, Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// Error or warning reported to the user.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Identifier of the problem kind, e.g. `broken-link`.
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    fn new(
+        severity: Severity,
+        code: &'static str,
+        message: String,
+        location: Option<&SourceLocation>,
+        file: Option<&Path>,
+    ) -> Self {
+        // error messages are prefixed with their location, which is shown
+        // separately
+        let prefix = match (location, file) {
+            (Some(location), _) => Some(format!("{}: ", location)),
+            (None, Some(file)) => Some(format!("{}: ", file.display())),
+            (None, None) => None,
+        };
+        let message = match prefix.as_deref().and_then(|it| message.strip_prefix(it)) {
+            Some(it) => it.to_string(),
+            None => message,
+        };
+
+        Diagnostic {
+            severity,
+            code,
+            message,
+            // content read from stdin has no path
+            file: location
+                .map(|it| it.path.as_path())
+                .or(file)
+                .filter(|it| !it.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+            span: location.map(|it| Span {
+                line: it.line,
+                column: it.column,
+                end_line: it.line,
+                end_column: it.column + it.length.max(1) - 1,
+            }),
+        }
+    }
+
+    pub fn error(err: &BlogError) -> Self {
+        let (location, file) = match err {
+            BlogError::Format(it) => (it.location(), it.path()),
+            BlogError::InvalidRoot(path) | BlogError::MissingTemplates(path) => {
+                (None, Some(path.as_path()))
+            }
+            _ => (None, None),
+        };
+        Diagnostic::new(Severity::Error, err.code(), err.to_string(), location, file)
+    }
+
+    pub fn warning(warning: &FormatWarning) -> Self {
+        let location = warning.location();
+        Diagnostic::new(
+            Severity::Warning,
+            warning.code(),
+            warning.to_string(),
+            Some(location),
+            Some(&location.path),
+        )
+    }
+
+    /// Sets the reported file if the diagnostic doesn't already point to one.
+    pub fn in_file(mut self, path: impl AsRef<Path>) -> Self {
+        if self.file.is_none() {
+            self.file = Some(path.as_ref().to_path_buf());
+        }
+        self
+    }
+
+    /// Prints the diagnostic to stderr, or as a JSON line to stdout if it
+    /// isn't used for generated output.
+    pub fn emit(&self, args: &Args) {
+        match args.message_format {
+            MessageFormat::Human => eprintln!("{}", self.render()),
+            MessageFormat::Json => match serde_json::to_string(self) {
+                Ok(it) if args.prints_output() => eprintln!("{}", it),
+                Ok(it) => println!("{}", it),
+                Err(err) => log::error!("unable to serialize diagnostic: {}", err),
+            },
+        }
+    }
+
+    /// Formats the diagnostic in rustc style, quoting the source line it
+    /// points to.
+    pub fn render(&self) -> String {
+        let severity = match self.severity {
+            Severity::Error => style("error").red().bold(),
+            Severity::Warning => style("warning").yellow().bold(),
+        }
+        .for_stderr();
+        let mut result = format!(
+            "{}{}",
+            severity,
+            style(format!("[{}]: {}", self.code, self.message))
+                .bold()
+                .for_stderr()
+        );
+
+        let file = match &self.file {
+            Some(it) => it,
+            None => return result,
+        };
+        let span = match &self.span {
+            Some(it) => it,
+            None => {
+                result.push_str(&format!("\n {} {}", gutter("-->"), file.display()));
+                return result;
+            }
+        };
+
+        let width = span.line.to_string().len();
+        let pad = " ".repeat(width);
+        result.push_str(&format!(
+            "\n{}{} {}:{}:{}",
+            pad,
+            gutter("-->"),
+            file.display(),
+            span.line,
+            span.column
+        ));
+
+        let line = std::fs::read_to_string(file)
+            .ok()
+            .and_then(|it| it.lines().nth(span.line - 1).map(str::to_string));
+        if let Some(line) = line {
+            let line = line.trim_end();
+            // keep tabs so the marker lines up with the quoted line
+            let indent: String = line
+                .chars()
+                .take(span.column.saturating_sub(1))
+                .map(|it| if it == '\t' { '\t' } else { ' ' })
+                .collect();
+            let end = if span.end_line == span.line {
+                span.end_column
+            } else {
+                line.chars().count()
+            };
+            let marker = "^".repeat((end + 1).saturating_sub(span.column).max(1));
+            let marker = match self.severity {
+                Severity::Error => style(marker).red().bold(),
+                Severity::Warning => style(marker).yellow().bold(),
+            }
+            .for_stderr();
+
+            result.push_str(&format!("\n{} {}", pad, gutter("|")));
+            result.push_str(&format!("\n{} {} {}", gutter(span.line), gutter("|"), line));
+            result.push_str(&format!("\n{} {} {}{}", pad, gutter("|"), indent, marker));
+        }
+        result
+    }
+}
+
+fn gutter(text: impl ToString) -> String {
+    style(text.to_string()).blue().bold().for_stderr().to_string()
+}
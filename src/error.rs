@@ -1,44 +1,273 @@
-use std::path::PathBuf;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use thiserror::Error;
 
+use crate::schema::FieldType;
+
+/// Position in a source file; line and column are 1-based.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// Number of characters the location spans, 0 if only the start is known.
+    pub length: usize,
+}
+
+impl SourceLocation {
+    pub fn new(path: impl AsRef<Path>, line: usize, column: usize) -> Self {
+        SourceLocation {
+            path: path.as_ref().to_path_buf(),
+            line,
+            column,
+            length: 0,
+        }
+    }
+
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum FormatError {
     #[error("post is empty")]
     EmptyPost,
     #[error("unclosed frontmatter header")]
     UnclosedFrontmatter,
+    #[error("{location}: custom field `{field}` should be {expected}, found {found}")]
+    FieldType {
+        location: SourceLocation,
+        field: String,
+        expected: FieldType,
+        found: &'static str,
+    },
+    #[error("{}: slug `{slug}` can only contain lowercase letters, digits, `-` and `_`", path.display())]
+    InvalidSlug { path: PathBuf, slug: String },
+    #[error("{}: can't expand permalink placeholder `:{placeholder}`: {reason}", path.display())]
+    Permalink {
+        path: PathBuf,
+        placeholder: String,
+        reason: &'static str,
+    },
+    #[error("URL `{0}` can't contain `..` segments")]
+    InvalidUrl(String),
+    #[error("{}: missing required custom field `{field}`", path.display())]
+    MissingField { path: PathBuf, field: String },
     #[error("unable to parse {invalid} DateTime")]
     DateTimeParse {
         invalid: String,
         source: chrono::ParseError,
     },
 
+    #[error("{location}: referenced file `{reference}` doesn't exist or is ignored")]
+    MissingAsset {
+        location: SourceLocation,
+        reference: String,
+    },
+    #[error("{}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    #[error("unsupported AST version {found}, expected {expected}")]
+    AstVersion { found: u32, expected: u32 },
+
     #[error(transparent)]
     TemplateError(#[from] handlebars::TemplateError),
+    #[error("{}: {source}", path.display())]
+    Template {
+        path: PathBuf,
+        source: handlebars::TemplateError,
+    },
     #[error(transparent)]
     Render(#[from] handlebars::RenderError),
 
+    #[error("{location}: {source}")]
+    Yaml {
+        location: SourceLocation,
+        source: serde_yaml::Error,
+    },
     #[error(transparent)]
-    Yaml(#[from] serde_yaml::Error),
+    Toml(#[from] toml::de::Error),
     #[error(transparent)]
     Json(#[from] serde_json::Error),
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
 
+impl FormatError {
+    /// Creates a YAML error for a document that starts after `line_offset`
+    /// lines of `path`.
+    pub fn yaml(path: impl AsRef<Path>, line_offset: usize, source: serde_yaml::Error) -> Self {
+        let (line, column) = match source.location() {
+            Some(it) => (line_offset + it.line(), it.column()),
+            None => (line_offset + 1, 1),
+        };
+        FormatError::Yaml {
+            location: SourceLocation::new(path, line, column),
+            source,
+        }
+    }
+
+    /// Identifier of the error kind, shown in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FormatError::EmptyPost => "empty-post",
+            FormatError::UnclosedFrontmatter => "unclosed-frontmatter",
+            FormatError::FieldType { .. } => "field-type",
+            FormatError::InvalidSlug { .. } => "invalid-slug",
+            FormatError::Permalink { .. } => "permalink",
+            FormatError::InvalidUrl(_) => "invalid-url",
+            FormatError::MissingField { .. } => "missing-field",
+            FormatError::DateTimeParse { .. } => "invalid-date",
+            FormatError::MissingAsset { .. } => "missing-asset",
+            FormatError::Image { .. } => "image",
+            FormatError::AstVersion { .. } => "ast-version",
+            FormatError::TemplateError(_) | FormatError::Template { .. } => "template",
+            FormatError::Render(_) => "render",
+            FormatError::Yaml { .. } => "invalid-yaml",
+            FormatError::Toml(_) => "invalid-toml",
+            FormatError::Json(_) => "invalid-json",
+            FormatError::Io(_) => "io",
+        }
+    }
+
+    /// Returns position the error was found at, if it's known.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        match self {
+            FormatError::FieldType { location, .. }
+            | FormatError::MissingAsset { location, .. }
+            | FormatError::Yaml { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Returns file the error was found in, if it's known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            FormatError::InvalidSlug { path, .. }
+            | FormatError::Permalink { path, .. }
+            | FormatError::MissingField { path, .. }
+            | FormatError::Image { path, .. }
+            | FormatError::Template { path, .. } => Some(path),
+            _ => self.location().map(|it| it.path.as_path()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FormatWarning {
+    #[error("{location}: unknown frontmatter key `{key}` (declare it in schema.yaml to use it as a custom field)")]
+    UnknownKey {
+        location: SourceLocation,
+        key: String,
+    },
+    #[error("{location}: link to `{link}` doesn't point to a post")]
+    BrokenLink {
+        location: SourceLocation,
+        link: String,
+    },
+    #[error("{location}: link to `{link}` points to a missing anchor `#{anchor}`")]
+    MissingAnchor {
+        location: SourceLocation,
+        link: String,
+        anchor: String,
+    },
+    #[error("{location}: link to `{link}` points to an unpublished post")]
+    DraftLink {
+        location: SourceLocation,
+        link: String,
+    },
+    #[error("{location}: external link to `{link}` {reason}")]
+    ExternalLink {
+        location: SourceLocation,
+        link: String,
+        reason: &'static str,
+    },
+    #[error("{location}: local time `{value}` doesn't exist in {timezone} timezone, using `{used}`")]
+    SkippedTime {
+        location: SourceLocation,
+        value: String,
+        timezone: String,
+        used: String,
+    },
+}
+
+impl FormatWarning {
+    /// Identifier of the warning kind, shown in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FormatWarning::UnknownKey { .. } => "unknown-key",
+            FormatWarning::BrokenLink { .. } => "broken-link",
+            FormatWarning::MissingAnchor { .. } => "missing-anchor",
+            FormatWarning::DraftLink { .. } => "draft-link",
+            FormatWarning::ExternalLink { .. } => "external-link",
+            FormatWarning::SkippedTime { .. } => "skipped-time",
+        }
+    }
+
+    pub fn location(&self) -> &SourceLocation {
+        match self {
+            FormatWarning::UnknownKey { location, .. }
+            | FormatWarning::BrokenLink { location, .. }
+            | FormatWarning::MissingAnchor { location, .. }
+            | FormatWarning::DraftLink { location, .. }
+            | FormatWarning::ExternalLink { location, .. }
+            | FormatWarning::SkippedTime { location, .. } => location,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum UserError {
     #[error("provided repository url ({0}) is invalid")]
     InvalidRepoUrl(String),
 }
 
+/// Multiple sources that would be written to the same output file.
+#[derive(Debug)]
+pub struct OutputCollision {
+    pub target: String,
+    pub sources: Vec<PathBuf>,
+}
+
+impl Display for OutputCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` is generated from:", self.target)?;
+        for source in &self.sources {
+            write!(f, "\n  - {}", source.display())?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum BlogError {
     #[error("provided repository ({expected}) doesn't match existing one ({existing})")]
     RepoMismatch { expected: String, existing: String },
     #[error("provided root directory path doesn't exist or points to a file: {0}")]
     InvalidRoot(PathBuf),
+    #[error("strict mode: {0} warning(s) reported")]
+    StrictWarnings(usize),
+    #[error("multiple sources map to the same output path:\n{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    OutputCollisions(Vec<OutputCollision>),
+    #[error("{0} post(s) failed to build")]
+    BuildFailed(usize),
+    #[error("template directory {} doesn't exist", .0.display())]
+    MissingTemplates(PathBuf),
+    #[error("`{0}` command isn't implemented yet")]
+    NotImplemented(&'static str),
 
     #[error(transparent)]
     Format(#[from] FormatError),
@@ -47,5 +276,27 @@ pub enum BlogError {
     #[error(transparent)]
     Git(#[from] git2::Error),
     #[error(transparent)]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    #[error(transparent)]
     Io(#[from] std::io::Error),
 }
+
+impl BlogError {
+    /// Identifier of the error kind, shown in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BlogError::RepoMismatch { .. } => "repo-mismatch",
+            BlogError::InvalidRoot(_) => "invalid-root",
+            BlogError::StrictWarnings(_) => "strict",
+            BlogError::OutputCollisions(_) => "output-collision",
+            BlogError::BuildFailed(_) => "build-failed",
+            BlogError::MissingTemplates(_) => "missing-templates",
+            BlogError::NotImplemented(_) => "not-implemented",
+            BlogError::Format(it) => it.code(),
+            BlogError::User(UserError::InvalidRepoUrl(_)) => "invalid-repo-url",
+            BlogError::Git(_) => "git",
+            BlogError::ThreadPool(_) => "thread-pool",
+            BlogError::Io(_) => "io",
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use crate::component::{Component, PostComponent, Style, TextComponent, TextPart};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcerptOptions {
+    /// Maximum number of paragraphs in excerpts of posts without a
+    /// `<!-- more -->` marker.
+    pub paragraphs: usize,
+    /// Maximum number of words in excerpts of posts without a marker.
+    pub words: usize,
+}
+
+impl Default for ExcerptOptions {
+    fn default() -> Self {
+        ExcerptOptions {
+            paragraphs: 1,
+            words: 60,
+        }
+    }
+}
+
+/// Teaser of a post, shown in listings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Excerpt {
+    pub html: String,
+    pub text: String,
+}
+
+/// Returns true if `component` is a `<!-- more -->` cut marker on its own
+/// line.
+fn is_more_marker(component: &PostComponent) -> bool {
+    match component {
+        PostComponent::Raw(raw) => {
+            let normalized: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+            normalized.eq_ignore_ascii_case("<!--more-->")
+        }
+        _ => false,
+    }
+}
+
+fn is_paragraph(component: &PostComponent) -> bool {
+    match component {
+        PostComponent::Text(TextComponent {
+            style: Style::Paragraph,
+            ..
+        }) => true,
+        PostComponent::Chained(items) => items.iter().any(is_paragraph),
+        _ => false,
+    }
+}
+
+/// Collapses whitespace and limits `text` to `words` words.
+fn limit_words(text: &str, words: usize) -> String {
+    let mut iter = text.split_whitespace();
+    let mut result = iter.by_ref().take(words).collect::<Vec<_>>().join(" ");
+    if iter.next().is_some() {
+        result.push('…');
+    }
+    result
+}
+
+/// Cuts `part` off after `remaining` words, ending it with `…`.
+///
+/// Returns true if anything was removed.
+fn truncate_words(part: &mut TextPart, remaining: &mut usize) -> bool {
+    match part {
+        TextPart::Raw(text) => {
+            let count = text.split_whitespace().count();
+            if count <= *remaining {
+                *remaining -= count;
+                return false;
+            }
+            let end = match remaining.checked_sub(1) {
+                Some(last) => text
+                    .split_whitespace()
+                    .nth(last)
+                    .map(|word| word.as_ptr() as usize - text.as_ptr() as usize + word.len())
+                    .unwrap_or_default(),
+                None => 0,
+            };
+            text.truncate(end);
+            text.push('…');
+            *remaining = 0;
+            true
+        }
+        TextPart::Chained(items) => {
+            for i in 0..items.len() {
+                if truncate_words(&mut items[i], remaining) {
+                    items.truncate(i + 1);
+                    return true;
+                }
+            }
+            false
+        }
+        TextPart::Nested(text) => truncate_words(&mut text.content, remaining),
+        TextPart::Empty | TextPart::NewLine => false,
+    }
+}
+
+impl Excerpt {
+    /// Builds an excerpt from post components.
+    ///
+    /// Content before a `<!-- more -->` marker is used if the post contains
+    /// one, otherwise first paragraphs up to limits in `options`. Headings,
+    /// images, footnotes, code blocks, admonition titles and raw HTML are left
+    /// out.
+    pub fn new(components: &[PostComponent], options: &ExcerptOptions) -> Excerpt {
+        let mut result = Excerpt::default();
+
+        match components.iter().position(is_more_marker) {
+            Some(end) => {
+                for it in &components[..end] {
+                    result.append(it);
+                }
+            }
+            None => {
+                let mut remaining = options.words;
+                let paragraphs = components.iter().filter(|it| is_paragraph(it));
+                for it in paragraphs.take(options.paragraphs.max(1)) {
+                    if remaining == 0 {
+                        break;
+                    }
+                    result.append_limited(it, &mut remaining);
+                }
+            }
+        }
+        result.text = limit_words(&result.text, usize::MAX);
+
+        result
+    }
+
+    /// Appends `component`, cutting its text off after `remaining` words.
+    fn append_limited(&mut self, component: &PostComponent, remaining: &mut usize) {
+        match component {
+            PostComponent::Text(text) if !matches!(text.style, Style::Heading { .. }) => {
+                let mut text = text.clone();
+                truncate_words(&mut text.content, remaining);
+                self.append(&PostComponent::Text(text));
+            }
+            PostComponent::Chained(items) => {
+                for it in items {
+                    if *remaining == 0 {
+                        break;
+                    }
+                    self.append_limited(it, remaining);
+                }
+            }
+            other => self.append(other),
+        }
+    }
+
+    fn append(&mut self, component: &PostComponent) {
+        match component {
+            PostComponent::Text(TextComponent {
+                style: Style::Heading { .. },
+                ..
+            })
+            | PostComponent::Image { .. }
+            | PostComponent::CodeBlock { .. }
+            | PostComponent::Footnote { .. }
+            | PostComponent::Placeholder
+            | PostComponent::Raw(_) => {}
+            // only the content, titles aren't part of the text
+            PostComponent::Admonition(it) => {
+                for it in &it.content {
+                    self.append(it);
+                }
+            }
+            PostComponent::Chained(items) => {
+                for it in items {
+                    self.append(it);
+                }
+            }
+            other => {
+                other
+                    .render(&mut self.html)
+                    .expect("post component render should be infallible");
+                other.write_plain(&mut self.text);
+            }
+        }
+    }
+}
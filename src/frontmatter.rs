@@ -0,0 +1,326 @@
+use std::{ops::Range, path::Path};
+
+use pulldown_cmark::{Event, MetadataBlockKind, Options, Parser, Tag, TagEnd};
+use serde::de::DeserializeOwned;
+
+use crate::error::{FormatError, FormatWarning, SourceLocation};
+
+const BOM: char = '\u{feff}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontmatterFormat {
+    /// Delimited by `---` lines.
+    Yaml,
+    /// Delimited by `+++` lines.
+    Toml,
+    /// A JSON object at the start of the file.
+    Json,
+}
+
+impl From<MetadataBlockKind> for FrontmatterFormat {
+    fn from(kind: MetadataBlockKind) -> Self {
+        match kind {
+            MetadataBlockKind::YamlStyle => FrontmatterFormat::Yaml,
+            MetadataBlockKind::PlusesStyle => FrontmatterFormat::Toml,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Frontmatter {
+    pub format: FrontmatterFormat,
+    /// Frontmatter content without delimiters.
+    pub content: String,
+    /// Zero based source line on which `content` starts.
+    pub line: usize,
+    /// Byte range of the whole block in the source, including delimiters and
+    /// the trailing line ending.
+    pub span: Range<usize>,
+}
+
+/// Returns `source` without the leading byte order mark.
+pub fn strip_bom(source: &str) -> &str {
+    source.strip_prefix(BOM).unwrap_or(source)
+}
+
+/// Returns zero based line number of byte `offset` in `source`.
+pub fn line_of(source: &str, offset: usize) -> usize {
+    source.as_bytes()[..offset]
+        .iter()
+        .filter(|it| **it == b'\n')
+        .count()
+}
+
+fn skip_line_ending(source: &str, offset: usize) -> usize {
+    let rest = &source[offset..];
+    if rest.starts_with("\r\n") {
+        offset + 2
+    } else if rest.starts_with('\n') {
+        offset + 1
+    } else {
+        offset
+    }
+}
+
+fn looks_like_json(source: &str) -> bool {
+    match source.strip_prefix('{') {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            rest.starts_with('"') || rest.starts_with('}')
+        }
+        None => false,
+    }
+}
+
+impl Frontmatter {
+    /// Finds frontmatter at the start of `source`.
+    ///
+    /// Leading blank lines are skipped, but `source` is expected not to start
+    /// with a BOM (see [`strip_bom`]).
+    pub fn find(source: &str) -> Result<Option<Frontmatter>, FormatError> {
+        let mut start = 0;
+        for line in source.split_inclusive('\n') {
+            if !line.trim().is_empty() {
+                break;
+            }
+            start += line.len();
+        }
+        let rest = &source[start..];
+
+        if looks_like_json(rest) {
+            let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<serde_json::Value>();
+            if let Some(Err(err)) = stream.next() {
+                return Err(err.into());
+            }
+            let end = start + stream.byte_offset();
+
+            return Ok(Some(Frontmatter {
+                format: FrontmatterFormat::Json,
+                content: source[start..end].to_string(),
+                line: line_of(source, start),
+                span: start..skip_line_ending(source, end),
+            }));
+        }
+
+        let options = Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+            | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+        let mut events = Parser::new_ext(rest, options).into_offset_iter();
+
+        let (kind, range) = match events.next() {
+            Some((Event::Start(Tag::MetadataBlock(kind)), range)) => (kind, range),
+            _ => {
+                let mut lines = rest.lines();
+                let first = lines.next().unwrap_or_default().trim_end();
+                let has_content = lines.next().map(|it| !it.trim().is_empty());
+                if (first == "---" || first == "+++") && has_content == Some(true) {
+                    return Err(FormatError::UnclosedFrontmatter);
+                }
+                return Ok(None);
+            }
+        };
+
+        let mut content = String::with_capacity(range.len());
+        for (event, _) in events {
+            match event {
+                Event::Text(text) => content.push_str(&text),
+                Event::End(TagEnd::MetadataBlock(_)) => break,
+                _ => {}
+            }
+        }
+
+        Ok(Some(Frontmatter {
+            format: kind.into(),
+            content,
+            line: line_of(source, start) + 1,
+            span: start..skip_line_ending(source, start + range.end),
+        }))
+    }
+
+    /// Deserializes frontmatter content, reporting keys that weren't
+    /// consumed by `T` as warnings.
+    ///
+    /// `path` is only used for error and warning locations.
+    pub fn parse<T: DeserializeOwned>(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(T, Vec<FormatWarning>), FormatError> {
+        let path = path.as_ref();
+        let mut ignored = Vec::new();
+        let track = |key: serde_ignored::Path| ignored.push(key.to_string());
+
+        let value = match self.format {
+            FrontmatterFormat::Yaml => {
+                let de = serde_yaml::Deserializer::from_str(&self.content);
+                serde_ignored::deserialize(de, track)
+                    .map_err(|err| FormatError::yaml(path, self.line, err))?
+            }
+            FrontmatterFormat::Toml => {
+                serde_ignored::deserialize(toml::Deserializer::new(&self.content), track)?
+            }
+            FrontmatterFormat::Json => {
+                let mut de = serde_json::Deserializer::from_str(&self.content);
+                let value = serde_ignored::deserialize(&mut de, track)?;
+                de.end()?;
+                value
+            }
+        };
+
+        let warnings = ignored
+            .into_iter()
+            .map(|key| FormatWarning::UnknownKey {
+                location: SourceLocation::new(path, self.key_line(&key), 1),
+                key,
+            })
+            .collect();
+
+        Ok((value, warnings))
+    }
+
+    /// Returns 1-based source line on which top level `key` is defined.
+    ///
+    /// For nested keys (e.g. `edits.0.time`) line of the top level key is
+    /// returned. If the key can't be found, first line of content is returned.
+    pub fn key_line(&self, key: &str) -> usize {
+        let key = key.split('.').next().unwrap_or(key);
+        let found = self.content.lines().position(|line| {
+            let line = match self.format {
+                FrontmatterFormat::Json => line.trim_start(),
+                _ => line,
+            };
+            let line = line.trim_start_matches(['"', '\'']);
+            match line.strip_prefix(key) {
+                Some(rest) => rest
+                    .trim_start_matches(['"', '\''])
+                    .trim_start()
+                    .starts_with([':', '=']),
+                None => false,
+            }
+        });
+
+        self.line + found.unwrap_or_default() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Info {
+        title: String,
+    }
+
+    fn find(source: &str) -> Frontmatter {
+        Frontmatter::find(source)
+            .expect("invalid frontmatter")
+            .expect("no frontmatter found")
+    }
+
+    #[test]
+    fn yaml() {
+        let source = "---\ntitle: Post\n---\nBody\n";
+        let frontmatter = find(source);
+        assert_eq!(frontmatter.format, FrontmatterFormat::Yaml);
+        assert_eq!(frontmatter.content, "title: Post\n");
+        assert_eq!(frontmatter.line, 1);
+        assert_eq!(&source[frontmatter.span.end..], "Body\n");
+    }
+
+    #[test]
+    fn crlf() {
+        let source = "---\r\ntitle: Post\r\n---\r\nBody\r\n";
+        let frontmatter = find(source);
+        assert_eq!(frontmatter.content.trim_end(), "title: Post");
+        assert_eq!(&source[frontmatter.span.end..], "Body\r\n");
+    }
+
+    #[test]
+    fn bom() {
+        let source = strip_bom("\u{feff}---\ntitle: Post\n---\nBody\n");
+        assert_eq!(find(source).content, "title: Post\n");
+    }
+
+    #[test]
+    fn delimiter_in_string() {
+        let source = "---\ntitle: \"a --- b\"\n---\nBody\n";
+        let (info, _) = find(source).parse::<Info>("post.md").unwrap();
+        assert_eq!(info.title, "a --- b");
+    }
+
+    #[test]
+    fn leading_blank_lines() {
+        let source = "\n\n---\ntitle: Post\n---\nBody\n";
+        let frontmatter = find(source);
+        assert_eq!(frontmatter.line, 3);
+        assert_eq!(frontmatter.span.start, 2);
+    }
+
+    #[test]
+    fn short_sources() {
+        for source in ["", "a", "# A", "---", "---\n", "+++"] {
+            assert!(
+                matches!(Frontmatter::find(source), Ok(None)),
+                "found frontmatter in {:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn unclosed() {
+        assert!(matches!(
+            Frontmatter::find("---\ntitle: Post\n"),
+            Err(FormatError::UnclosedFrontmatter)
+        ));
+    }
+
+    #[test]
+    fn toml() {
+        let source = "+++\ntitle = \"Post\"\n+++\nBody\n";
+        let frontmatter = find(source);
+        assert_eq!(frontmatter.format, FrontmatterFormat::Toml);
+        let (info, warnings) = frontmatter.parse::<Info>("post.md").unwrap();
+        assert_eq!(info.title, "Post");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn json() {
+        let source = "{\n  \"title\": \"Post\"\n}\nBody\n";
+        let frontmatter = find(source);
+        assert_eq!(frontmatter.format, FrontmatterFormat::Json);
+        assert_eq!(frontmatter.line, 0);
+        assert_eq!(&source[frontmatter.span.end..], "Body\n");
+        let (info, _) = frontmatter.parse::<Info>("post.md").unwrap();
+        assert_eq!(info.title, "Post");
+    }
+
+    #[test]
+    fn not_json() {
+        assert!(matches!(Frontmatter::find("{not json} text"), Ok(None)));
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let source = "---\ntitle: Post\n\ncolour: red\n---\n";
+        let (_, warnings) = find(source).parse::<Info>("post.md").unwrap();
+        assert_eq!(warnings.len(), 1);
+        match &warnings[0] {
+            FormatWarning::UnknownKey { key, location } => {
+                assert_eq!(key, "colour");
+                assert_eq!(location.line, 4);
+            }
+            other => panic!("unexpected warning: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn line_offsets() {
+        let source = "a\nb\r\nc";
+        assert_eq!(line_of(source, 0), 0);
+        assert_eq!(line_of(source, 2), 1);
+        assert_eq!(line_of(source, source.len()), 2);
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset,
+    error::FormatError,
+    util::{content_hash, random_id},
+};
+
+/// Default variant cache directory, relative to blog root.
+pub const DEFAULT_CACHE_DIR: &str = ".cache/images";
+
+/// Extensions of images that can be measured and resized.
+const RASTER_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Format of generated image variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariantFormat {
+    Webp,
+    Avif,
+}
+
+impl VariantFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VariantFormat::Webp => "webp",
+            VariantFormat::Avif => "avif",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            VariantFormat::Webp => "image/webp",
+            VariantFormat::Avif => "image/avif",
+        }
+    }
+
+    fn image_format(&self) -> ImageFormat {
+        match self {
+            VariantFormat::Webp => ImageFormat::WebP,
+            VariantFormat::Avif => ImageFormat::Avif,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageOptions {
+    /// Widths (in pixels) of generated variants. Widths larger than the
+    /// original image are skipped.
+    pub widths: Vec<u32>,
+    /// Formats variants are generated in, preferred first. Set to an empty
+    /// list to only add image dimensions.
+    pub formats: Vec<VariantFormat>,
+    /// Value of the `sizes` attribute of generated sources.
+    pub sizes: String,
+    /// Directory (relative to blog root) generated variants are cached in;
+    /// [`DEFAULT_CACHE_DIR`] if not set.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            widths: vec![480, 960, 1440],
+            formats: vec![VariantFormat::Webp],
+            sizes: "100vw".to_string(),
+            cache_dir: None,
+        }
+    }
+}
+
+impl ImageOptions {
+    /// Returns the directory variants are cached in.
+    pub fn cache_dir(&self, root: &Path) -> PathBuf {
+        match &self.cache_dir {
+            Some(it) => root.join(it),
+            None => root.join(DEFAULT_CACHE_DIR),
+        }
+    }
+}
+
+/// Alternative source of an image in a single format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageSource {
+    /// MIME type of the source, e.g. `image/webp`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub srcset: String,
+}
+
+/// Dimensions and resized variants of a local raster image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsiveImage {
+    pub width: u32,
+    pub height: u32,
+    pub sizes: String,
+    pub sources: Vec<ImageSource>,
+}
+
+/// Returns true if the image at `path` can be measured and resized.
+pub fn is_raster(path: &Path) -> bool {
+    match path.extension().and_then(|it| it.to_str()) {
+        Some(ext) => RASTER_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()),
+        None => false,
+    }
+}
+
+/// Reads dimensions of local images and generates their resized variants,
+/// which are copied to the output directory by [`ImageProcessor::write`].
+///
+/// Encoded variants are cached by content hash of the original image, so
+/// unchanged images are only copied on later builds. Images can be processed
+/// from multiple threads; each one is processed only once.
+pub struct ImageProcessor<'a> {
+    root: &'a Path,
+    target_dir: &'a Path,
+    cache_dir: PathBuf,
+    options: &'a ImageOptions,
+    /// Results for already processed images, by path relative to `root`.
+    processed: Mutex<HashMap<PathBuf, Arc<Mutex<Option<ResponsiveImage>>>>>,
+    /// Variant path (relative to target directory) -> cached variant.
+    variants: Mutex<HashMap<PathBuf, PathBuf>>,
+}
+
+impl<'a> ImageProcessor<'a> {
+    pub fn new(root: &'a Path, target_dir: &'a Path, options: &'a ImageOptions) -> Self {
+        ImageProcessor {
+            root,
+            target_dir,
+            cache_dir: options.cache_dir(root),
+            options,
+            processed: Mutex::new(HashMap::new()),
+            variants: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Processes image at `relative` path (relative to blog root), returning
+    /// its dimensions and generated variants.
+    pub fn process(&self, relative: &Path) -> Result<ResponsiveImage, FormatError> {
+        let entry = self
+            .processed
+            .lock()
+            .expect("image cache poisoned")
+            .entry(relative.to_path_buf())
+            .or_default()
+            .clone();
+        // held while processing so other posts using the image wait for it
+        let mut entry = entry.lock().expect("image cache poisoned");
+        if let Some(it) = entry.as_ref() {
+            return Ok(it.clone());
+        }
+
+        let path = self.root.join(relative);
+        let image_error = |source| FormatError::Image {
+            path: path.clone(),
+            source,
+        };
+
+        let content = std::fs::read(&path)?;
+        let hash = content_hash(&content);
+        let reader = ImageReader::new(Cursor::new(&content)).with_guessed_format()?;
+        // resizing animated images would keep only the first frame
+        let animated = reader.format() == Some(ImageFormat::Gif);
+        let (width, height) = reader.into_dimensions().map_err(image_error)?;
+
+        let mut widths: Vec<u32> = self
+            .options
+            .widths
+            .iter()
+            .copied()
+            .filter(|it| *it > 0 && *it < width)
+            .chain(std::iter::once(width))
+            .collect();
+        widths.sort_unstable();
+        widths.dedup();
+
+        let cache_dir = &self.cache_dir;
+        let stem = relative
+            .file_stem()
+            .map(|it| it.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut decoded: Option<DynamicImage> = None;
+        let mut sources = vec![];
+        for format in self.options.formats.iter().filter(|_| !animated) {
+            let mut srcset = vec![];
+            for &variant_width in &widths {
+                let ext = format.extension();
+                let cached = cache_dir.join(format!("{}-{}.{}", hash, variant_width, ext));
+                if !cached.exists() {
+                    let image = match &mut decoded {
+                        Some(it) => it,
+                        None => decoded.insert(image::load_from_memory(&content).map_err(image_error)?),
+                    };
+                    let variant_height =
+                        ((height as u64 * variant_width as u64) / width as u64).max(1) as u32;
+                    let resized = image.resize_exact(variant_width, variant_height, FilterType::Lanczos3);
+
+                    let mut encoded = Cursor::new(vec![]);
+                    DynamicImage::ImageRgba8(resized.to_rgba8())
+                        .write_to(&mut encoded, format.image_format())
+                        .map_err(image_error)?;
+                    // identical images may be encoded concurrently, so the
+                    // cache entry is replaced atomically
+                    let partial = cached.with_extension(format!("{}-{}", ext, random_id()));
+                    std::fs::create_dir_all(&cache_dir)?;
+                    std::fs::write(&partial, encoded.into_inner())?;
+                    std::fs::rename(&partial, &cached)?;
+                }
+
+                let variant = relative.with_file_name(format!(
+                    "{}-{}w-{}.{}",
+                    stem,
+                    variant_width,
+                    &hash[..8],
+                    ext
+                ));
+                srcset.push(format!("{} {}w", asset::url(&variant), variant_width));
+                self.variants
+                    .lock()
+                    .expect("image cache poisoned")
+                    .insert(variant, cached);
+            }
+            sources.push(ImageSource {
+                kind: format.mime().to_string(),
+                srcset: srcset.join(", "),
+            });
+        }
+
+        let result = ResponsiveImage {
+            width,
+            height,
+            sizes: self.options.sizes.clone(),
+            sources,
+        };
+        *entry = Some(result.clone());
+        Ok(result)
+    }
+
+    /// Copies generated variants to the output directory, returning their
+    /// number.
+    pub fn write(&self) -> Result<usize, FormatError> {
+        let variants = self.variants.lock().expect("image cache poisoned");
+        for (variant, cached) in variants.iter() {
+            let target = self.target_dir.join(variant);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(cached, target)?;
+        }
+        Ok(variants.len())
+    }
+}
@@ -0,0 +1,44 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use serde::Serialize;
+
+use crate::{error::FormatError, excerpt::Excerpt, post::PostInfo, stats::PostStats};
+
+/// Name of the generated post index in the target directory.
+pub const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Serialize)]
+pub struct PostIndexEntry {
+    #[serde(flatten)]
+    pub info: PostInfo,
+    /// Root relative URL of the post.
+    pub url: String,
+    /// Output file path, relative to target directory.
+    pub path: String,
+    pub excerpt: Excerpt,
+    pub stats: PostStats,
+}
+
+/// List of generated posts, written as JSON for static consumption.
+#[derive(Debug, Default, Serialize)]
+pub struct PostIndex {
+    pub posts: Vec<PostIndexEntry>,
+}
+
+impl PostIndex {
+    pub fn new() -> PostIndex {
+        PostIndex::default()
+    }
+
+    pub fn push(&mut self, entry: PostIndexEntry) {
+        self.posts.push(entry);
+    }
+
+    pub fn write(&mut self, target_dir: impl AsRef<Path>) -> Result<(), FormatError> {
+        self.posts.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let writer = BufWriter::new(File::create(target_dir.as_ref().join(INDEX_FILE))?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
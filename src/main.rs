@@ -1,103 +1,660 @@
-use arguments::{Args, Verb};
+use arguments::{Args, OutputFormat, RenderArgs, RenderFormat, RenderTemplate, Verb};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use chrono::{DateTime, Utc};
 use clap::Parser;
-use error::BlogError;
+use handlebars::Handlebars;
+use diagnostic::Diagnostic;
+use error::{BlogError, FormatError, OutputCollision};
+use images::ImageProcessor;
+use index::{PostIndex, PostIndexEntry};
+use links::PostLinks;
+use permalink::Permalink;
+use progress::{BuildSummary, IndexSummary, OutputMode, Progress};
+use rayon::prelude::*;
+use redirect::Redirect;
+use template::{article::RedirectTemplate, Generate};
+use watch::Snapshot;
 use post::RawPostContent;
 
-use crate::{blog::Blog, post::Post};
+use crate::{
+    blog::{Blog, FileIndex, IndexData},
+    post::Post,
+};
 
 pub(crate) mod arguments;
+pub mod asset;
 pub mod blog;
 pub mod component;
+pub mod config;
+pub mod date;
+pub mod diagnostic;
 pub mod error;
+pub mod excerpt;
+pub mod frontmatter;
+pub mod images;
+pub mod index;
+pub mod links;
+pub mod permalink;
 pub mod post;
+pub mod progress;
+pub mod redirect;
+pub mod schema;
+pub mod slug;
+pub mod stats;
 pub mod template;
 pub mod util;
+pub mod watch;
 
 fn main() {
-    env_logger::builder().init();
-
     let args = Args::parse();
 
-    let mut blog = Blog::open(&args.working_dir).expect("unable to open blog directory");
+    let level = if args.verbose {
+        log::LevelFilter::Info
+    } else if args.quiet {
+        log::LevelFilter::Error
+    } else {
+        log::LevelFilter::Warn
+    };
+    env_logger::builder()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+
+    if let Err(err) = run(&args) {
+        Diagnostic::error(&err).emit(&args);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), BlogError> {
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+
+    match &args.verb {
+        Verb::Watch => return watch(args),
+        Verb::Clone(_) => return Err(BlogError::NotImplemented("clone")),
+        Verb::Pull => return Err(BlogError::NotImplemented("pull")),
+        Verb::Posts => return Err(BlogError::NotImplemented("posts")),
+        Verb::Publish => return Err(BlogError::NotImplemented("publish")),
+        Verb::Index | Verb::Build | Verb::Render(_) => {}
+    }
+
+    let mut blog = Blog::open(&args.working_dir)?;
+
+    blog.load_target_metadata(&args.target_dir)?;
+
+    load_templates(&blog, args)?;
+
+    match &args.verb {
+        Verb::Build => {
+            build(&mut blog, args)?;
+            blog.write_target_metadata(&args.target_dir)?;
+        }
+        Verb::Index => index(&mut blog, args)?,
+        Verb::Render(options) => render(&blog, args, options)?,
+        // handled before opening the blog
+        Verb::Watch | Verb::Clone(_) | Verb::Pull | Verb::Posts | Verb::Publish => {}
+    };
+    Ok(())
+}
 
-    blog.load_target_metadata(&args.target_dir)
-        .expect("unable to load blog metadata");
+/// Returns directory user templates are loaded from.
+fn templates_dir(blog: &Blog, args: &Args) -> PathBuf {
+    args.templates
+        .clone()
+        .unwrap_or_else(|| blog.source_dir.join(template::TEMPLATE_DIR))
+}
 
-    /*
-    let index = blog.file_index.get_or_insert_with(FileIndex::new);
-    for f in blog.sources() {
-        index.note(f.path());
+/// Registers user templates, if there are any.
+///
+/// In watch mode templates are also reloaded whenever they're rendered.
+fn load_templates(blog: &Blog, args: &Args) -> Result<(), BlogError> {
+    let templates = templates_dir(blog, args);
+    if templates.is_dir() {
+        let dev_mode = matches!(args.verb, Verb::Watch);
+        template::load_dir(&templates, dev_mode)?;
+    } else if args.templates.is_some() {
+        return Err(BlogError::MissingTemplates(templates));
     }
-    */
+    Ok(())
+}
 
-    match args.verb {
-        Verb::Build => build(&mut blog, &args),
-        _ => todo!(),
+/// Builds the blog and rebuilds it whenever a file in the blog root or
+/// templates directory changes, until interrupted.
+///
+/// Blog configuration and templates are reloaded for every build, and errors
+/// are reported without stopping.
+fn watch(args: &Args) -> Result<(), BlogError> {
+    let mut dirs = vec![args.working_dir.clone()];
+    dirs.extend(args.templates.clone());
+    let exclude = [args.target_dir.clone()];
+
+    let rebuild = || -> Result<(), BlogError> {
+        let mut blog = Blog::open(&args.working_dir)?;
+        blog.load_target_metadata(&args.target_dir)?;
+        // templates deleted since the last build must not stay registered
+        template::reset();
+        load_templates(&blog, args)?;
+        build(&mut blog, args)?;
+        blog.write_target_metadata(&args.target_dir)?;
+        Ok(())
+    };
+
+    if !args.quiet {
+        eprintln!("Watching {} for changes", args.working_dir.display());
     }
-    .unwrap();
+    let mut snapshot = None;
+    loop {
+        let current = Snapshot::take(&dirs, &exclude);
+        if snapshot.as_ref() == Some(&current) {
+            std::thread::sleep(watch::POLL_INTERVAL);
+            continue;
+        }
+        snapshot = Some(current);
 
-    blog.write_target_metadata(&args.target_dir)
-        .expect("unable to write blog metadata");
+        if let Err(err) = rebuild() {
+            Diagnostic::error(&err).emit(args);
+        }
+    }
 }
 
 fn build(blog: &mut Blog, args: &Args) -> Result<(), BlogError> {
+    let start = Instant::now();
+    let mode = OutputMode::detect(args.quiet, args.verbose);
+    let mut summary = BuildSummary::default();
     std::fs::create_dir_all(&args.target_dir)?;
 
     let reg = template::engine().read().expect("engine poisoned");
 
+    let now = build_time(blog, args)?;
+
+    let templates = templates_dir(blog, args);
+    let image_cache = blog.config.images.cache_dir(&blog.source_dir);
+    let assets = asset::collect(
+        &blog.source_dir,
+        &[templates, args.target_dir.clone(), image_cache],
+    );
+    let images = ImageProcessor::new(&blog.source_dir, &args.target_dir, &blog.config.images);
+    let mut links = PostLinks::new(&blog.source_dir);
+
     log::info!("Loading new posts:");
-    let mut errors = vec![];
-    for source in blog.sources() {
-        let path = source.path().to_path_buf();
-        log::info!("- {}", path.to_string_lossy());
+    let mut sources: Vec<PathBuf> = blog.sources().map(|it| it.path().to_path_buf()).collect();
+    sources.sort();
 
-        let source_name = path
-            .file_name()
-            .expect("no file name")
-            .to_str()
-            .expect("non UTF-8 name")
-            .to_string();
+    let blog = &*blog;
+    let progress = Progress::new(mode, "Loading", sources.len());
+    let loaded: Vec<_> = sources
+        .par_iter()
+        .map(|path| {
+            let result = load_post(path, blog, args, now, &assets, &images);
+            progress.inc();
+            result
+        })
+        .collect();
+    progress.finish();
 
-        let raw = match RawPostContent::open(&path) {
+    let mut index = PostIndex::new();
+    let mut warning_count = 0;
+    let mut posts = vec![];
+    let mut targets: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    // The post index is always written, so posts can't take its place.
+    targets.insert(PathBuf::from(index::INDEX_FILE), vec![PathBuf::from("(post index)")]);
+    for (path, result) in sources.iter().zip(loaded) {
+        log::info!("- {}", path.to_string_lossy());
+
+        let (post, published) = match result {
             Ok(it) => it,
             Err(err) => {
-                errors.push((source_name, err));
+                report_error(args, path, &err);
+                summary.failed += 1;
                 continue;
             }
         };
 
-        let post = match Post::new(raw) {
-            Ok(it) => it,
-            Err(err) => {
-                errors.push((source_name, err));
+        for warning in &post.warnings {
+            Diagnostic::warning(warning).emit(args);
+        }
+        warning_count += post.warnings.len();
+
+        let (slug, permalink) = match published {
+            Some(Ok(it)) => it,
+            Some(Err(err)) => {
+                report_error(args, path, &err);
+                summary.failed += 1;
+                continue;
+            }
+            None => {
+                log::info!("  skipped: not published at {}", now);
+                links.add_draft(&post.path);
+                summary.skipped += 1;
                 continue;
             }
         };
+        targets
+            .entry(permalink.path.clone())
+            .or_insert_with(Vec::new)
+            .push(path.clone());
+        posts.push((permalink, slug, post));
+    }
+
+    let history = if blog.config.redirects.history {
+        let sources: Vec<PathBuf> = posts.iter().map(|(_, _, post)| post.path.clone()).collect();
+        let cache = blog.source_dir.join(redirect::HISTORY_CACHE);
+        redirect::previous_slugs(&blog.source_dir, &sources, cache).unwrap_or_else(|err| {
+            log::debug!("unable to read git history: {}", err);
+            HashMap::new()
+        })
+    } else {
+        HashMap::new()
+    };
 
-        let data = post.template_ctx();
+    // Redirect pages are always HTML, whatever the posts are rendered as.
+    let mut redirects = vec![];
+    for (permalink, slug, post) in &posts {
+        let resolve_slug = |alias: &str| {
+            Permalink::resolve(
+                blog.config.permalink(),
+                blog.config.pretty_urls,
+                "html",
+                post,
+                alias,
+                &blog.source_dir,
+            )
+        };
 
-        let rendered = reg
-            .render("article", &data)
-            .map_err(|err| BlogError::Format(err.into()))?;
+        for alias in &post.info.aliases {
+            let from = if alias.starts_with('/') {
+                Permalink::from_url(alias, blog.config.pretty_urls, "html")
+            } else if slug::is_valid(alias) {
+                resolve_slug(alias)
+            } else {
+                Err(FormatError::InvalidSlug {
+                    path: post.path.clone(),
+                    slug: alias.clone(),
+                })
+            };
+            let from = match from {
+                Ok(it) => it,
+                Err(err) => {
+                    report_error(args, &post.path, &err.into());
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            if from.path == permalink.path {
+                continue;
+            }
 
-        let target_name = data
-            .info
-            .slug
-            .or_else(|| source_name.split(".").next().map(|it| it.to_string()))
-            .unwrap_or_else(|| "output".to_string());
+            targets
+                .entry(from.path.clone())
+                .or_insert_with(Vec::new)
+                .push(post.path.clone());
+            redirects.push(Redirect {
+                from,
+                to: permalink.url.clone(),
+            });
+        }
 
-        std::fs::write(
-            args.target_dir.join(target_name + "." + &args.ext),
-            rendered,
-        );
+        // Previous locations are only used if nothing else is generated there.
+        for previous in history.get(&post.path).into_iter().flatten() {
+            let from = match resolve_slug(previous) {
+                Ok(it) if previous != slug && !targets.contains_key(&it.path) => it,
+                _ => continue,
+            };
+            targets.insert(from.path.clone(), vec![post.path.clone()]);
+            redirects.push(Redirect {
+                from,
+                to: permalink.url.clone(),
+            });
+        }
+    }
+
+    let mut collisions: Vec<_> = targets
+        .into_iter()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(target, sources)| OutputCollision {
+            target: target.to_string_lossy().to_string(),
+            sources,
+        })
+        .collect();
+    collisions.sort_by(|a, b| a.target.cmp(&b.target));
+    if !collisions.is_empty() {
+        return Err(BlogError::OutputCollisions(collisions));
+    }
+
+    let anchors: Vec<_> = posts.par_iter().map(|(_, _, post)| post.anchors()).collect();
+    for ((permalink, _, post), anchors) in posts.iter().zip(anchors) {
+        links.add(&post.path, &permalink.url, anchors);
+    }
+    let link_warnings: Vec<_> = posts
+        .par_iter_mut()
+        .map(|(_, _, post)| post.resolve_links(&links))
+        .collect();
+    let mut broken_links = 0;
+    for warning in link_warnings.into_iter().flatten() {
+        Diagnostic::warning(&warning).emit(args);
+        broken_links += 1;
     }
+    if broken_links > 0 {
+        log::info!("{} broken internal link(s)", broken_links);
+        warning_count += broken_links;
+    }
+
+    set_helper_data(
+        blog,
+        posts
+            .iter()
+            .map(|(permalink, slug, _)| (slug.clone(), permalink.url.clone()))
+            .collect(),
+    );
 
-    if errors.is_empty() {
-        log::info!("Following errors occurred during build:");
-        for (name, err) in errors {
-            log::info!("- {}: {}", name, err)
+    let reg = &*reg;
+    let progress = Progress::new(mode, "Rendering", posts.len());
+    let rendered: Vec<_> = posts
+        .into_par_iter()
+        .map(|(permalink, slug, post)| {
+            let path = post.path.clone();
+            let result =
+                render_post(post, permalink, slug, reg, blog, args).map_err(|err| (path, err));
+            progress.inc();
+            result
+        })
+        .collect();
+    progress.finish();
+    let mut stdout = std::io::stdout().lock();
+    for result in rendered {
+        match result {
+            Ok((entry, output)) => {
+                if let Some(output) = output {
+                    print_output(&mut stdout, &output)?;
+                }
+                index.push(entry);
+            }
+            Err((path, err)) => {
+                report_error(args, &path, &err);
+                summary.failed += 1;
+            }
         }
     }
+    summary.built = index.posts.len();
+
+    let fingerprinted = std::mem::take(
+        &mut *template::helpers::data()
+            .read()
+            .expect("helper data poisoned")
+            .assets
+            .lock()
+            .expect("helper data poisoned"),
+    );
+    asset::copy(&blog.source_dir, &args.target_dir, &assets)?;
+    asset::copy_fingerprinted(&blog.source_dir, &args.target_dir, &fingerprinted)?;
+    let variants = images.write()?;
+
+    for redirect in &redirects {
+        let page = RedirectTemplate {
+            target: redirect.to.clone(),
+            delay: "0".to_string(),
+            head: Some(format!(
+                "<link rel=\"canonical\" href=\"{}\" />",
+                handlebars::html_escape(&redirect.to)
+            )),
+        };
+        page.generate(&reg, args.target_dir.join(&redirect.from.path))?;
+    }
+    redirect::write_server_files(&redirects, &blog.config.redirects.server, &args.target_dir)?;
+
+    index.write(&args.target_dir)?;
+
+    summary.warnings = warning_count;
+    let pages = if args.print_output { 0 } else { summary.built };
+    summary.outputs = pages
+        + redirects.len()
+        + assets.len()
+        + fingerprinted.len()
+        + variants
+        + blog.config.redirects.server.len()
+        + 1;
+    summary.elapsed = start.elapsed();
+
+    summary.print(mode);
+
+    if summary.failed > 0 {
+        return Err(BlogError::BuildFailed(summary.failed));
+    }
+
+    if args.strict && warning_count > 0 {
+        return Err(BlogError::StrictWarnings(warning_count));
+    }
 
     Ok::<(), BlogError>(())
 }
+
+/// Records creation and modification times of post sources in the file
+/// index, which is saved in the target directory.
+fn index(blog: &mut Blog, args: &Args) -> Result<(), BlogError> {
+    let start = Instant::now();
+    let mode = OutputMode::detect(args.quiet, args.verbose);
+
+    let mut sources: Vec<PathBuf> = blog.sources().map(|it| it.path().to_path_buf()).collect();
+    sources.sort();
+
+    let progress = Progress::new(mode, "Indexing", sources.len());
+    let mut index = FileIndex::new();
+    for path in &sources {
+        log::info!("- {}", path.to_string_lossy());
+        index.note(path);
+        progress.inc();
+    }
+    progress.finish();
+    blog.file_index = Some(index);
+
+    std::fs::create_dir_all(&args.target_dir)?;
+    blog.write_target_metadata(&args.target_dir)?;
+
+    IndexSummary {
+        indexed: sources.len(),
+        elapsed: start.elapsed(),
+    }
+    .print(mode);
+    Ok(())
+}
+
+/// Returns the time post publication is checked against.
+fn build_time(blog: &Blog, args: &Args) -> Result<DateTime<Utc>, BlogError> {
+    Ok(match &args.now {
+        Some(it) => date::parse_date(it, blog.config.timezone())?.with_timezone(&Utc),
+        None => Utc::now(),
+    })
+}
+
+/// Fills in build state used by template helpers; `posts` maps slugs of
+/// published posts to their URLs.
+fn set_helper_data(blog: &Blog, posts: HashMap<String, String>) {
+    let mut data = template::helpers::data().write().expect("helper data poisoned");
+    data.root = blog.source_dir.clone();
+    data.timezone = blog.config.timezone;
+    data.tag_url = blog.config.tag_url.clone();
+    data.posts = posts;
+}
+
+/// Returns URLs of published posts by their slugs, skipping posts that can't
+/// be loaded.
+fn published_urls(blog: &Blog, args: &Args, now: DateTime<Utc>) -> HashMap<String, String> {
+    let sources: Vec<PathBuf> = blog.sources().map(|it| it.path().to_path_buf()).collect();
+    sources
+        .par_iter()
+        .filter_map(|path| {
+            let post = Post::new(RawPostContent::open(path).ok()?, &blog.config).ok()?;
+            if !post.info.is_published(now) {
+                return None;
+            }
+            let slug = post.slug().ok()?;
+            let permalink = Permalink::resolve(
+                blog.config.permalink(),
+                blog.config.pretty_urls,
+                args.extension(),
+                &post,
+                &slug,
+                &blog.source_dir,
+            )
+            .ok()?;
+            Some((slug, permalink.url))
+        })
+        .collect()
+}
+
+/// Reports an error found while building post with source at `path`.
+fn report_error(args: &Args, path: &Path, err: &BlogError) {
+    Diagnostic::error(err).in_file(path).emit(args);
+}
+
+/// Parsed post and, if it's published, its slug and output location.
+type LoadedPost = (Post, Option<Result<(String, Permalink), BlogError>>);
+
+/// Reads and parses a single post source, preparing it for rendering.
+fn load_post(
+    path: &Path,
+    blog: &Blog,
+    args: &Args,
+    now: DateTime<Utc>,
+    assets: &[PathBuf],
+    images: &ImageProcessor,
+) -> Result<LoadedPost, BlogError> {
+    let raw = RawPostContent::open(path)?;
+    let mut post = Post::new(raw, &blog.config)?;
+    if !post.info.is_published(now) {
+        return Ok((post, None));
+    }
+
+    let prepared = post
+        .resolve_assets(&blog.source_dir, assets)
+        .and_then(|_| post.process_images(images, assets))
+        .and_then(|_| {
+            let slug = post.slug()?;
+            let permalink = Permalink::resolve(
+                blog.config.permalink(),
+                blog.config.pretty_urls,
+                args.extension(),
+                &post,
+                &slug,
+                &blog.source_dir,
+            )?;
+            Ok((slug, permalink))
+        })
+        .map_err(BlogError::from);
+    Ok((post, Some(prepared)))
+}
+
+/// Renders a post in requested output format and writes it to the target
+/// directory, returning its index entry.
+///
+/// If output is printed to stdout, rendered post is returned instead of
+/// written.
+fn render_post(
+    mut post: Post,
+    permalink: Permalink,
+    slug: String,
+    reg: &Handlebars,
+    blog: &Blog,
+    args: &Args,
+) -> Result<(PostIndexEntry, Option<String>), BlogError> {
+    post.info.slug = Some(slug);
+    post.url = Some(permalink.url.clone());
+    post.update_excerpt(&blog.config.excerpt);
+    let modified = match blog.file_index.as_ref().and_then(|it| it.get(&post.path)) {
+        Some(it) => it.modified(),
+        None => IndexData::new(&post.path).modified(),
+    };
+    let excerpt = post.excerpt.clone();
+    let stats = post.stats.clone();
+    let (rendered, info) = match args.format() {
+        OutputFormat::Html => {
+            let data = post.template_ctx();
+            let rendered = template::layout::render_page(reg, &blog.config, &data, modified)?;
+            (rendered, data.info)
+        }
+        OutputFormat::Svelte => (post.svelte_component(&blog.config.svelte), post.info),
+        OutputFormat::Ast => {
+            let rendered =
+                serde_json::to_string_pretty(&post.document()).map_err(FormatError::from)?;
+            (rendered, post.info)
+        }
+    };
+
+    let target = args.target_dir.join(&permalink.path);
+    let entry = PostIndexEntry {
+        info,
+        url: permalink.url,
+        path: permalink.path.to_string_lossy().replace('\\', "/"),
+        excerpt,
+        stats,
+    };
+    if args.print_output {
+        return Ok((entry, Some(rendered)));
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(target, rendered)?;
+    Ok((entry, None))
+}
+
+/// Renders a single post read from a file or stdin and prints it to stdout.
+///
+/// Assets, images and links to other posts aren't resolved, but template
+/// helpers (e.g. `url_for`) can refer to other published posts.
+fn render(blog: &Blog, args: &Args, options: &RenderArgs) -> Result<(), BlogError> {
+    let raw = if options.input == Path::new("-") {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        RawPostContent::from(content)
+    } else {
+        RawPostContent::open(&options.input)?
+    };
+    let post = Post::new(raw, &blog.config)?;
+    for warning in &post.warnings {
+        Diagnostic::warning(warning).emit(args);
+    }
+    let now = build_time(blog, args)?;
+    set_helper_data(blog, published_urls(blog, args, now));
+
+    let output = if options.frontmatter_only {
+        serde_json::to_string_pretty(&post.info).map_err(FormatError::from)?
+    } else {
+        match options.output {
+            RenderFormat::Html => match options.template {
+                RenderTemplate::Article => {
+                    let reg = template::engine().read().expect("engine poisoned");
+                    template::layout::render_page(&reg, &blog.config, &post.template_ctx(), None)?
+                }
+                RenderTemplate::None => post.template_ctx().content,
+            },
+            RenderFormat::Ast => {
+                serde_json::to_string_pretty(&post.document()).map_err(FormatError::from)?
+            }
+            RenderFormat::Text => post.plain_text(),
+        }
+    };
+
+    print_output(&mut std::io::stdout().lock(), &output)?;
+    Ok(())
+}
+
+/// Prints generated output, ignoring stdout being closed early (e.g. when
+/// piped to `head`).
+fn print_output(out: &mut impl Write, output: &str) -> std::io::Result<()> {
+    match writeln!(out, "{}", output.trim_end()) {
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
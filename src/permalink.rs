@@ -0,0 +1,225 @@
+use std::path::{Component, Path, PathBuf};
+
+use chrono::Datelike;
+use serde::Serialize;
+
+use crate::{error::FormatError, post::Post, slug};
+
+/// Permalink pattern used when none is configured; keeps output flat.
+pub const DEFAULT_PATTERN: &str = "/:slug";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Permalink {
+    /// Root relative URL of the post.
+    pub url: String,
+    /// Output file path, relative to target directory.
+    pub path: PathBuf,
+}
+
+/// Source directory components of `path` relative to blog `root`.
+fn source_dirs(path: &Path, root: &Path) -> Vec<String> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let parent = relative.parent().unwrap_or(Path::new(""));
+
+    parent
+        .components()
+        .filter_map(|it| match it {
+            Component::Normal(name) => Some(slug::slugify(name.to_string_lossy())),
+            _ => None,
+        })
+        .filter(|it| !it.is_empty())
+        .collect()
+}
+
+impl Permalink {
+    /// Creates a permalink for root relative `url`.
+    ///
+    /// URLs ending with `/`, and URLs without an extension if `pretty` is set,
+    /// are written to `index.<ext>` in a directory named by the URL.
+    ///
+    /// URLs with `..` segments are rejected as they could point outside of
+    /// the target directory.
+    pub fn from_url(url: &str, pretty: bool, ext: &str) -> Result<Permalink, FormatError> {
+        let segments: Vec<&str> = url.split('/').filter(|it| !it.is_empty()).collect();
+        if segments.contains(&"..") {
+            return Err(FormatError::InvalidUrl(url.to_string()));
+        }
+        let mut path = segments.join("/");
+        let has_extension = segments.last().map(|it| it.contains('.')).unwrap_or_default();
+
+        let url = if url.ends_with('/') || segments.is_empty() {
+            let url = format!("/{}/", path).replace("//", "/");
+            path = format!("{}/index.{}", path, ext);
+            url
+        } else if has_extension {
+            format!("/{}", path)
+        } else if pretty {
+            let url = format!("/{}/", path);
+            path = format!("{}/index.{}", path, ext);
+            url
+        } else {
+            path = format!("{}.{}", path, ext);
+            format!("/{}", path)
+        };
+
+        Ok(Permalink {
+            url,
+            path: PathBuf::from(path.trim_start_matches('/')),
+        })
+    }
+
+    /// Expands permalink `pattern` for a post.
+    ///
+    /// Supported placeholders are `:year`, `:month`, `:day` (publication
+    /// date), `:section` (top level source directory), `:path` (source
+    /// directory), `:tag` (first tag) and `:slug`.
+    ///
+    /// Expanded pattern is converted into output path with
+    /// [`Permalink::from_url`].
+    pub fn resolve(
+        pattern: &str,
+        pretty: bool,
+        ext: &str,
+        post: &Post,
+        slug: &str,
+        root: impl AsRef<Path>,
+    ) -> Result<Permalink, FormatError> {
+        let date = post.info.published();
+        let dirs = source_dirs(&post.path, root.as_ref());
+
+        let mut expanded = String::with_capacity(pattern.len() + slug.len());
+        let mut rest = pattern;
+        while let Some(start) = rest.find(':') {
+            expanded.push_str(&rest[..start]);
+            let name_len = rest[start + 1..]
+                .find(|c: char| !c.is_ascii_alphabetic() && c != '_')
+                .unwrap_or(rest.len() - start - 1);
+            let name = &rest[start + 1..start + 1 + name_len];
+            rest = &rest[start + 1 + name_len..];
+
+            let missing = |reason| FormatError::Permalink {
+                path: post.path.clone(),
+                placeholder: name.to_string(),
+                reason,
+            };
+
+            let value = match name {
+                "year" => format!("{:04}", date.ok_or_else(|| missing("post has no date"))?.year()),
+                "month" => format!("{:02}", date.ok_or_else(|| missing("post has no date"))?.month()),
+                "day" => format!("{:02}", date.ok_or_else(|| missing("post has no date"))?.day()),
+                "section" => dirs.first().cloned().unwrap_or_default(),
+                "path" => dirs.join("/"),
+                "tag" => post
+                    .info
+                    .tags
+                    .first()
+                    .map(slug::slugify)
+                    .ok_or_else(|| missing("post has no tags"))?,
+                "slug" => slug.to_string(),
+                _ => return Err(missing("unknown placeholder")),
+            };
+            expanded.push_str(&value);
+        }
+        expanded.push_str(rest);
+
+        Permalink::from_url(&expanded, pretty, ext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::BlogConfig, post::RawPostContent};
+
+    fn post(path: &str, frontmatter: &str) -> Post {
+        let raw = RawPostContent {
+            inner: format!("---\n{}---\nText\n", frontmatter),
+            path: PathBuf::from(path),
+            line_offset: 0,
+        };
+        Post::new(raw, &BlogConfig::default()).expect("unable to parse post")
+    }
+
+    fn resolve(pattern: &str, post: &Post) -> Result<Permalink, FormatError> {
+        Permalink::resolve(pattern, false, "html", post, "hello", "blog")
+    }
+
+    #[test]
+    fn urls() {
+        let permalink = Permalink::from_url("/a/b", false, "html").unwrap();
+        assert_eq!(permalink.url, "/a/b.html");
+        assert_eq!(permalink.path, PathBuf::from("a/b.html"));
+
+        let permalink = Permalink::from_url("/a/b", true, "html").unwrap();
+        assert_eq!(permalink.url, "/a/b/");
+        assert_eq!(permalink.path, PathBuf::from("a/b/index.html"));
+
+        let permalink = Permalink::from_url("/a/b/", false, "svelte").unwrap();
+        assert_eq!(permalink.url, "/a/b/");
+        assert_eq!(permalink.path, PathBuf::from("a/b/index.svelte"));
+
+        let permalink = Permalink::from_url("/feed.xml", true, "html").unwrap();
+        assert_eq!(permalink.url, "/feed.xml");
+        assert_eq!(permalink.path, PathBuf::from("feed.xml"));
+
+        let permalink = Permalink::from_url("/", true, "html").unwrap();
+        assert_eq!(permalink.url, "/");
+        assert_eq!(permalink.path, PathBuf::from("index.html"));
+    }
+
+    #[test]
+    fn parent_segments() {
+        for url in ["/../a", "/a/../../b", "..", "/a/..//"] {
+            assert!(
+                matches!(
+                    Permalink::from_url(url, false, "html"),
+                    Err(FormatError::InvalidUrl(_))
+                ),
+                "{} wasn't rejected",
+                url
+            );
+        }
+        // dots in names are fine
+        assert!(Permalink::from_url("/a..b/c", false, "html").is_ok());
+    }
+
+    #[test]
+    fn placeholders() {
+        let post = post(
+            "blog/Rust Notes/async/post.md",
+            "date: 2023-04-02T10:00:00+02:00\ntags: [Zero Cost, other]\n",
+        );
+
+        let permalink = resolve("/:year/:month/:day/:slug", &post).unwrap();
+        assert_eq!(permalink.url, "/2023/04/02/hello.html");
+
+        let permalink = resolve("/:section/:slug/", &post).unwrap();
+        assert_eq!(permalink.url, "/rust-notes/hello/");
+        assert_eq!(permalink.path, PathBuf::from("rust-notes/hello/index.html"));
+
+        let permalink = resolve("/:path/:tag-:slug", &post).unwrap();
+        assert_eq!(permalink.url, "/rust-notes/async/zero-cost-hello.html");
+    }
+
+    #[test]
+    fn empty_placeholders() {
+        let post = post("blog/post.md", "title: Top level\n");
+
+        // missing directories collapse instead of leaving empty segments
+        let permalink = resolve("/:section/:slug", &post).unwrap();
+        assert_eq!(permalink.url, "/hello.html");
+
+        for (pattern, placeholder) in [
+            ("/:year/:slug", "year"),
+            ("/:tag/:slug", "tag"),
+            ("/:category/:slug", "category"),
+        ] {
+            match resolve(pattern, &post) {
+                Err(FormatError::Permalink {
+                    placeholder: it, ..
+                }) => assert_eq!(it, placeholder),
+                other => panic!("expected {} to fail, got {:?}", pattern, other),
+            }
+        }
+    }
+}
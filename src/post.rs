@@ -1,27 +1,55 @@
-use std::{convert::Infallible, default::Default, path::Path, str::FromStr, vec};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    default::Default,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+    vec,
+};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::{
-    component::{Component, Parser},
-    error::{BlogError, FormatError},
+    asset,
+    component::{
+        admonition,
+        parser::ParserOptions,
+        svelte::{SvelteOptions, SvelteRenderer},
+        urls::UrlKind,
+        Component, Parser, PostComponent,
+    },
+    config::BlogConfig,
+    date::PostDate,
+    excerpt::{Excerpt, ExcerptOptions},
+    error::{BlogError, FormatError, FormatWarning, SourceLocation},
+    frontmatter::{self, Frontmatter},
+    images::{self, ImageProcessor, ResponsiveImage},
+    links::{self, PostLinks},
+    schema::FrontmatterSchema,
+    slug,
+    stats::PostStats,
+    template::meta::script_json,
 };
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Edit {
     pub summary: String,
     pub time: DateTime<Utc>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Author {
     pub name: String,
     pub email: Option<String>,
     pub web: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum AuthorEntry {
     Name(String),
@@ -35,17 +63,57 @@ impl Default for AuthorEntry {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+impl AuthorEntry {
+    /// Returns all listed authors.
+    pub fn authors(&self) -> Vec<Author> {
+        match self {
+            AuthorEntry::Name(name) => vec![Author {
+                name: name.clone(),
+                ..Default::default()
+            }],
+            AuthorEntry::Author(it) => vec![it.clone()],
+            AuthorEntry::AuthorList(it) => it.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PostInfo {
     pub title: Option<String>,
     pub description: Option<String>,
     #[serde(default)]
     pub tags: Vec<String>,
     pub slug: Option<String>,
+    /// Previous URLs (starting with `/`) or slugs of the post which redirect
+    /// to the current one.
+    #[serde(default)]
+    pub aliases: Vec<String>,
     pub author: Option<AuthorEntry>,
     pub edits: Option<Vec<Edit>>,
+    /// Publication date; posts dated in the future aren't built.
+    pub date: Option<PostDate>,
+    pub updated: Option<PostDate>,
+    /// Date after which the post isn't built anymore.
+    pub expires: Option<PostDate>,
+    /// IANA timezone in which dates without an offset are interpreted.
+    /// Defaults to the timezone from blog configuration.
+    pub timezone: Option<Tz>,
+    /// Language of the post, defaults to the site language.
+    pub lang: Option<String>,
+    /// Name of the layout template the article is rendered into, or `none`
+    /// to output only the article.
+    pub layout: Option<String>,
+    /// Cover image URL, used for link previews.
+    pub image: Option<String>,
+    /// Custom fields, passed through to templates and index as is.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// Frontmatter key, raw value and resolved time of a local time skipped by a
+/// DST transition.
+pub type SkippedTime = (&'static str, String, DateTime<FixedOffset>);
+
 impl PostInfo {
     pub fn new() -> PostInfo {
         PostInfo {
@@ -53,10 +121,66 @@ impl PostInfo {
             description: None,
             tags: vec![],
             slug: None,
+            aliases: vec![],
             author: None,
             edits: None,
+            date: None,
+            updated: None,
+            expires: None,
+            timezone: None,
+            lang: None,
+            layout: None,
+            image: None,
+            extra: Map::new(),
         }
     }
+
+    /// Resolves dates without an explicit offset in post timezone, or
+    /// `default` timezone if post doesn't specify one.
+    ///
+    /// Returns local times skipped by DST transitions.
+    pub fn resolve_dates(&mut self, default: Tz) -> Result<Vec<SkippedTime>, FormatError> {
+        let tz = self.timezone.unwrap_or(default);
+        let mut skipped = Vec::new();
+        for (key, date) in [
+            ("date", &mut self.date),
+            ("updated", &mut self.updated),
+            ("expires", &mut self.expires),
+        ] {
+            let Some(date) = date else { continue };
+            let value = match date {
+                PostDate::Raw(it) => it.clone(),
+                PostDate::Resolved(_) => continue,
+            };
+            if date.resolve(tz)? {
+                if let PostDate::Resolved(time) = date {
+                    skipped.push((key, value, *time));
+                }
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// Returns publication date, falling back to the first edit.
+    pub fn published(&self) -> Option<DateTime<FixedOffset>> {
+        match &self.date {
+            Some(PostDate::Resolved(it)) => Some(*it),
+            _ => self
+                .edits
+                .as_ref()
+                .and_then(|it| it.first())
+                .map(|it| it.time.fixed_offset()),
+        }
+    }
+
+    /// Returns true if the post is published at time `now` and didn't
+    /// expire yet.
+    pub fn is_published(&self, now: DateTime<Utc>) -> bool {
+        let date = self.date.as_ref().and_then(PostDate::utc);
+        let expires = self.expires.as_ref().and_then(PostDate::utc);
+
+        date.map(|it| it <= now).unwrap_or(true) && expires.map(|it| it > now).unwrap_or(true)
+    }
 }
 
 pub trait MergeData<With> {
@@ -74,12 +198,37 @@ impl MergeData<PostInfo> for PostInfo {
         if let Some(it) = value.slug {
             self.slug = Some(it);
         }
+        if !value.aliases.is_empty() {
+            self.aliases = value.aliases;
+        }
         if let Some(it) = value.author {
             self.author = Some(it);
         }
         if let Some(it) = value.edits {
             self.edits = Some(it);
         }
+        if let Some(it) = value.date {
+            self.date = Some(it);
+        }
+        if let Some(it) = value.updated {
+            self.updated = Some(it);
+        }
+        if let Some(it) = value.expires {
+            self.expires = Some(it);
+        }
+        if let Some(it) = value.timezone {
+            self.timezone = Some(it);
+        }
+        if let Some(it) = value.lang {
+            self.lang = Some(it);
+        }
+        if let Some(it) = value.layout {
+            self.layout = Some(it);
+        }
+        if let Some(it) = value.image {
+            self.image = Some(it);
+        }
+        self.extra.extend(value.extra);
     }
 }
 
@@ -95,51 +244,80 @@ impl FromStr for PostInfo {
 #[serde(from = "String", into = "String")]
 pub struct RawPostContent {
     pub inner: String,
+    /// Source file path, empty if content wasn't read from a file.
+    pub path: PathBuf,
+    /// Number of source lines preceding `inner` (i.e. removed frontmatter).
+    pub line_offset: usize,
 }
 
 impl RawPostContent {
     pub fn new() -> RawPostContent {
         RawPostContent {
             inner: String::new(),
+            path: PathBuf::new(),
+            line_offset: 0,
         }
     }
 
     pub fn open(file: impl AsRef<Path>) -> Result<RawPostContent, BlogError> {
+        let file = file.as_ref();
         Ok(RawPostContent {
             inner: std::fs::read_to_string(file)?,
+            path: file.to_path_buf(),
+            line_offset: 0,
         })
     }
 
-    pub(crate) fn take_info(&mut self) -> Result<PostInfo, BlogError> {
-        if self.inner.len() <= 8 {
-            return Ok(PostInfo::default());
+    /// Removes frontmatter from content and parses it.
+    ///
+    /// Custom fields are validated against `schema` and dates are resolved in
+    /// `timezone` unless the post sets its own. Returned warnings contain keys
+    /// that aren't recognized and local times that don't exist.
+    pub(crate) fn take_info(
+        &mut self,
+        schema: Option<&FrontmatterSchema>,
+        timezone: Tz,
+    ) -> Result<(PostInfo, Vec<FormatWarning>), BlogError> {
+        let source = frontmatter::strip_bom(&self.inner);
+        let frontmatter = Frontmatter::find(source)?;
+
+        let (mut info, mut warnings) = match &frontmatter {
+            Some(it) => it.parse::<PostInfo>(&self.path)?,
+            None => (PostInfo::default(), vec![]),
+        };
+
+        if let Some(frontmatter) = &frontmatter {
+            let undeclared = info
+                .extra
+                .keys()
+                .filter(|key| !schema.map(|it| it.declares(key)).unwrap_or_default());
+            for key in undeclared {
+                warnings.push(FormatWarning::UnknownKey {
+                    location: SourceLocation::new(&self.path, frontmatter.key_line(key), 1),
+                    key: key.clone(),
+                });
+            }
         }
 
-        let frontmatter_start = self
-            .inner
-            .chars()
-            .enumerate()
-            .skip_while(|it| it.1.is_whitespace())
-            .next()
-            .map(|it| it.0 + 4)
-            .unwrap_or(4);
-
-        if !self.inner[frontmatter_start - 4..].starts_with("---\n") {
-            return Ok(PostInfo::default());
+        if let Some(schema) = schema {
+            schema.apply(&mut info.extra, &self.path, frontmatter.as_ref())?;
         }
 
-        let frontmatter_end = frontmatter_start
-            + self.inner[(frontmatter_start)..]
-                .find("---\n")
-                .ok_or(BlogError::Format(FormatError::UnclosedFrontmatter))?;
-
-        let frontmatter = &self.inner[frontmatter_start..frontmatter_end];
-        let result = PostInfo::from_str(frontmatter).unwrap_or_default();
+        for (key, value, time) in info.resolve_dates(timezone)? {
+            let line = frontmatter.as_ref().map(|it| it.key_line(key)).unwrap_or(1);
+            warnings.push(FormatWarning::SkippedTime {
+                location: SourceLocation::new(&self.path, line, 1),
+                value,
+                timezone: info.timezone.unwrap_or(timezone).name().to_string(),
+                used: time.naive_local().to_string(),
+            });
+        }
 
-        let content = &self.inner[(frontmatter_end + 4)..];
-        self.inner = content.to_string();
+        let body_start = frontmatter.map(|it| it.span.end).unwrap_or_default();
+        self.line_offset += frontmatter::line_of(source, body_start);
+        self.inner = source[body_start..].to_string();
 
-        Ok(result)
+        Ok((info, warnings))
     }
 }
 
@@ -151,7 +329,11 @@ impl AsRef<str> for RawPostContent {
 
 impl From<String> for RawPostContent {
     fn from(inner: String) -> Self {
-        RawPostContent { inner }
+        RawPostContent {
+            inner,
+            path: PathBuf::new(),
+            line_offset: 0,
+        }
     }
 }
 
@@ -167,6 +349,8 @@ impl FromStr for RawPostContent {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(RawPostContent {
             inner: s.to_string(),
+            path: PathBuf::new(),
+            line_offset: 0,
         })
     }
 }
@@ -174,32 +358,353 @@ impl FromStr for RawPostContent {
 #[derive(Debug)]
 pub struct Post {
     pub info: PostInfo,
+    /// Source file path, empty if post wasn't read from a file.
+    pub path: PathBuf,
     pub source: String,
+    /// Number of lines in the original file preceding `source`.
+    pub line_offset: usize,
+    /// Number of characters container expansion added before the content of
+    /// each line of `source`.
+    pub column_shifts: Vec<isize>,
+    /// Destination URLs of links and images with byte ranges in `source`.
+    pub link_ranges: Vec<(String, Range<usize>)>,
+    pub options: ParserOptions,
+    pub warnings: Vec<FormatWarning>,
+    /// Root relative URL of the generated page, once known.
+    pub url: Option<String>,
+    pub excerpt: Excerpt,
+    pub stats: PostStats,
+    /// Parsed content; URLs and image data are updated in place as they're
+    /// resolved.
+    pub content: Vec<PostComponent<'static>>,
+    /// Replacements for URLs in post content, e.g. relative asset paths.
+    pub urls: HashMap<String, String>,
+    /// Dimensions and variants of local images, by (replaced) image URL.
+    pub images: HashMap<String, ResponsiveImage>,
+}
+
+/// Version of the JSON AST schema, incremented on incompatible changes to
+/// [`PostDocument`] or serialized components.
+pub const AST_VERSION: u32 = 1;
+
+/// Post as a serializable component tree, written by the `ast` output format.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostDocument<'a> {
+    pub version: u32,
+    pub info: PostInfo,
+    pub url: Option<String>,
+    pub content: Vec<PostComponent<'a>>,
+}
+
+impl<'a> PostDocument<'a> {
+    /// Parses a JSON document, checking it uses the supported schema version.
+    pub fn from_json(json: &str) -> Result<PostDocument<'a>, FormatError> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } = serde_json::from_str(json)?;
+        if version != AST_VERSION {
+            return Err(FormatError::AstVersion {
+                found: version,
+                expected: AST_VERSION,
+            });
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SvelteMetadata<'a> {
+    #[serde(flatten)]
+    info: &'a PostInfo,
+    url: Option<&'a str>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostTemplateContext {
     #[serde(flatten)]
     pub info: PostInfo,
+    pub url: Option<String>,
+    pub excerpt: Excerpt,
+    pub stats: PostStats,
     pub content: String,
 }
 
 impl Post {
-    pub fn new(mut raw: RawPostContent) -> Result<Self, BlogError> {
-        Ok(Post {
-            info: raw.take_info()?,
-            source: raw.inner,
-        })
+    pub fn new(mut raw: RawPostContent, config: &BlogConfig) -> Result<Self, BlogError> {
+        let (info, warnings) = raw.take_info(config.schema.as_ref(), config.timezone())?;
+        let (source, column_shifts) =
+            admonition::expand_containers(&raw.inner, &config.markdown.admonitions);
+        let source = match source {
+            Cow::Borrowed(_) => raw.inner,
+            Cow::Owned(it) => it,
+        };
+
+        let mut parser = Parser::with_options(&source, config.markdown.clone());
+        let content: Vec<_> = parser.by_ref().map(PostComponent::into_owned).collect();
+        let link_ranges = parser.into_link_ranges();
+
+        let mut post = Post {
+            info,
+            path: raw.path,
+            source,
+            line_offset: raw.line_offset,
+            column_shifts,
+            link_ranges,
+            options: config.markdown.clone(),
+            warnings,
+            url: None,
+            excerpt: Excerpt::new(&content, &config.excerpt),
+            stats: PostStats::new(&content),
+            content,
+            urls: HashMap::new(),
+            images: HashMap::new(),
+        };
+
+        for (kind, url) in post.content_urls() {
+            if kind == UrlKind::Link {
+                if let Some(reason) = post.options.external_links.check(&url) {
+                    post.warnings.push(FormatWarning::ExternalLink {
+                        location: post.locate(&url),
+                        link: url,
+                        reason,
+                    });
+                }
+            }
+        }
+        if post.info.description.is_none() && !post.excerpt.text.is_empty() {
+            post.info.description = Some(post.excerpt.text.clone());
+        }
+
+        Ok(post)
     }
 
-    pub fn components(&self) -> Parser {
-        Parser::new(&self.source)
+    /// Returns location of the first link or image pointing to `url`, or of
+    /// content start if there's none.
+    pub fn locate(&self, url: &str) -> SourceLocation {
+        let range = self.link_ranges.iter().find_map(|(dest_url, range)| {
+            (dest_url == url || url.strip_prefix("mailto:") == Some(dest_url.as_str()))
+                .then(|| range.clone())
+        });
+        let range = match range {
+            Some(it) => it,
+            None => return SourceLocation::new(&self.path, self.line_offset + 1, 1),
+        };
+
+        // point to the URL if it's written out, otherwise to the whole link
+        let written = &self.source[range.clone()];
+        let (offset, length) = match written.rfind(url) {
+            Some(it) if !url.is_empty() => (range.start + it, url.chars().count()),
+            _ => (
+                range.start,
+                written.lines().next().unwrap_or_default().chars().count(),
+            ),
+        };
+
+        let line = frontmatter::line_of(&self.source, offset);
+        let line_start = self.source[..offset].rfind('\n').map(|it| it + 1).unwrap_or(0);
+        let shift = self.column_shifts.get(line).copied().unwrap_or_default();
+        let column = self.source[line_start..offset].chars().count() as isize - shift;
+        SourceLocation::new(
+            &self.path,
+            self.line_offset + line + 1,
+            column.max(0) as usize + 1,
+        )
+        .with_length(length)
+    }
+
+    /// Returns slug used for output file name.
+    ///
+    /// Slug is taken from frontmatter, or derived from title or source path.
+    pub fn slug(&self) -> Result<String, FormatError> {
+        slug::for_post(&self.info, &self.path)
+    }
+
+    /// Returns kinds and URLs of all links and images in the content.
+    fn content_urls(&mut self) -> Vec<(UrlKind, String)> {
+        let mut result = vec![];
+        for it in &mut self.content {
+            it.visit_urls(&mut |kind, url| {
+                result.push((kind, url.to_string()));
+                None
+            });
+        }
+        result
+    }
+
+    /// Replaces `urls` in the content and adds them to [`Post::urls`].
+    fn replace_urls(&mut self, urls: HashMap<String, String>) {
+        if urls.is_empty() {
+            return;
+        }
+        for it in &mut self.content {
+            it.visit_urls(&mut |_, url| urls.get(url).cloned());
+        }
+        self.urls.extend(urls);
+    }
+
+    /// Rebuilds the excerpt from current content, so it uses URLs replaced
+    /// by [`Post::resolve_assets`] and [`Post::resolve_links`].
+    pub fn update_excerpt(&mut self, options: &ExcerptOptions) {
+        self.excerpt = Excerpt::new(&self.content, options);
+    }
+
+    /// Maps relative image and file link URLs to locations of `assets`
+    /// copied to the output, failing if a referenced file isn't one of them.
+    ///
+    /// `assets` are sorted paths relative to blog `root`, as returned by
+    /// [`asset::collect`]. Links to pages (Markdown or HTML files and
+    /// extensionless paths) are left as is.
+    pub fn resolve_assets(
+        &mut self,
+        root: impl AsRef<Path>,
+        assets: &[PathBuf],
+    ) -> Result<(), FormatError> {
+        let root = root.as_ref();
+        let mut urls = HashMap::new();
+
+        for (kind, url) in self.content_urls() {
+            if !asset::is_relative(&url) || urls.contains_key(&url) {
+                continue;
+            }
+            let (path, suffix) = asset::split_suffix(&url);
+            if kind == UrlKind::Link && asset::is_page(path) {
+                continue;
+            }
+            match asset::resolve(root, &self.path, path) {
+                Some(it) if assets.binary_search(&it).is_ok() => {
+                    let resolved = asset::url(&it) + suffix;
+                    urls.insert(url, resolved);
+                }
+                _ => {
+                    return Err(FormatError::MissingAsset {
+                        location: self.locate(&url),
+                        reference: url,
+                    });
+                }
+            }
+        }
+
+        self.replace_urls(urls);
+        Ok(())
+    }
+
+    /// Returns ids of elements in the post that can be linked to.
+    pub fn anchors(&self) -> HashSet<String> {
+        let mut result = HashSet::new();
+        for component in &self.content {
+            component.visit_anchors(&mut |id| {
+                result.insert(id.to_string());
+            });
+        }
+        result
+    }
+
+    /// Maps links to Markdown sources of other posts to their output URLs.
+    ///
+    /// Returns warnings for links to missing or unpublished posts and for
+    /// anchors missing in the linked (or this) post.
+    pub fn resolve_links(&mut self, links: &PostLinks) -> Vec<FormatWarning> {
+        let anchors = self.anchors();
+        let mut urls = HashMap::new();
+        let mut warnings = vec![];
+        let mut checked = HashSet::new();
+
+        for (kind, url) in self.content_urls() {
+            if kind != UrlKind::Link || !checked.insert(url.clone()) {
+                continue;
+            }
+            let url = url.as_str();
+            let missing_anchor = |anchor: &str| FormatWarning::MissingAnchor {
+                location: self.locate(url),
+                link: url.to_string(),
+                anchor: anchor.to_string(),
+            };
+
+            if let Some(anchor) = url.strip_prefix('#') {
+                if !anchors.contains(anchor) {
+                    warnings.push(missing_anchor(anchor));
+                }
+                continue;
+            }
+            let (path, suffix) = asset::split_suffix(url);
+            if !asset::is_relative(url) || !links::is_markdown(path) {
+                continue;
+            }
+
+            let relative = asset::resolve(links.root(), &self.path, path);
+            match relative.as_deref().and_then(|it| links.get(it)) {
+                Some(target) => {
+                    if let Some((_, anchor)) = suffix.split_once('#') {
+                        if !target.anchors.contains(anchor) {
+                            warnings.push(missing_anchor(anchor));
+                        }
+                    }
+                    urls.insert(url.to_string(), target.url.clone() + suffix);
+                }
+                None if relative.map(|it| links.is_draft(&it)).unwrap_or_default() => {
+                    warnings.push(FormatWarning::DraftLink {
+                        location: self.locate(url),
+                        link: url.to_string(),
+                    });
+                }
+                None => warnings.push(FormatWarning::BrokenLink {
+                    location: self.locate(url),
+                    link: url.to_string(),
+                }),
+            }
+        }
+
+        self.replace_urls(urls);
+        warnings
+    }
+
+    /// Reads dimensions and generates variants of local raster images, i.e.
+    /// root relative image URLs pointing to one of `assets`.
+    ///
+    /// Should be called after [`Post::resolve_assets`].
+    pub fn process_images(
+        &mut self,
+        processor: &ImageProcessor,
+        assets: &[PathBuf],
+    ) -> Result<(), FormatError> {
+        let mut sources = vec![];
+        for component in &mut self.content {
+            component.visit_images(&mut |source, _| sources.push(source.to_string()));
+        }
+
+        for source in sources {
+            if self.images.contains_key(&source) || source.starts_with("//") {
+                continue;
+            }
+            let relative = match source.strip_prefix('/') {
+                Some(it) => PathBuf::from(asset::split_suffix(it).0),
+                None => continue,
+            };
+            if !images::is_raster(&relative) || assets.binary_search(&relative).is_err() {
+                continue;
+            }
+            let image = processor.process(&relative)?;
+            self.images.insert(source, image);
+        }
+
+        let images = &self.images;
+        for component in &mut self.content {
+            component.visit_images(&mut |source, responsive| {
+                if let Some(image) = images.get(source) {
+                    *responsive = Some(image.clone());
+                }
+            });
+        }
+        Ok(())
     }
 
     pub fn template_ctx(self) -> PostTemplateContext {
         let mut content = String::with_capacity(1024);
 
-        for c in self.components() {
+        for c in &self.content {
             c.render(&mut content)
                 .expect("post component render should be infallible");
         }
@@ -208,7 +713,122 @@ impl Post {
 
         PostTemplateContext {
             info: self.info,
+            url: self.url,
+            excerpt: self.excerpt,
+            stats: self.stats,
             content,
         }
     }
+
+    /// Returns text content of the post without any markup.
+    pub fn plain_text(&self) -> String {
+        let mut result = String::with_capacity(self.source.len());
+        for c in &self.content {
+            c.write_plain(&mut result);
+        }
+        result
+    }
+
+    /// Returns the post as a component tree document.
+    pub fn document(&self) -> PostDocument<'_> {
+        PostDocument {
+            version: AST_VERSION,
+            info: self.info.clone(),
+            url: self.url.clone(),
+            content: self.content.clone(),
+        }
+    }
+
+    /// Renders the post as a Svelte component.
+    ///
+    /// Post info and URL are exported as `metadata` from the module script.
+    pub fn svelte_component(&self, options: &SvelteOptions) -> String {
+        let mut renderer = SvelteRenderer::new(options);
+        let mut content = String::with_capacity(1024);
+        for c in &self.content {
+            renderer
+                .render(c, &mut content)
+                .expect("post component render should be infallible");
+        }
+
+        let metadata = serde_json::json!(SvelteMetadata {
+            info: &self.info,
+            url: self.url.as_deref(),
+        });
+
+        let mut result = String::with_capacity(content.len() + 512);
+        result.push_str("<script context=\"module\">\n");
+        result.push_str("  export const metadata = ");
+        result.push_str(&script_json(&metadata));
+        result.push_str(";\n</script>\n\n");
+        if !renderer.imports().is_empty() {
+            result.push_str("<script>\n");
+            for (name, path) in renderer.imports() {
+                result.push_str(&format!("  import {} from {};\n", name, script_json(&path.as_str().into())));
+            }
+            result.push_str("</script>\n\n");
+        }
+        result.push_str(&content);
+        result.push('\n');
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_round_trip() {
+        let raw = RawPostContent::open("test/input.md").expect("unable to read test post");
+        let post = Post::new(raw, &BlogConfig::default()).expect("unable to parse test post");
+        let document = post.document();
+
+        let json = serde_json::to_string(&document).unwrap();
+        let parsed = PostDocument::from_json(&json).expect("unable to parse AST");
+
+        assert_eq!(parsed.version, AST_VERSION);
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&document).unwrap()
+        );
+    }
+
+    fn take_info(source: &str) -> (PostInfo, Vec<FormatWarning>) {
+        let mut raw = RawPostContent::from(source.to_string());
+        raw.take_info(None, Tz::Europe__Berlin)
+            .expect("unable to parse frontmatter")
+    }
+
+    #[test]
+    fn timezone_override() {
+        let (info, warnings) =
+            take_info("---\ntimezone: Asia/Tokyo\ndate: 2023-04-02 10:00\n---\n");
+        assert!(warnings.is_empty());
+        assert_eq!(
+            info.published().unwrap().to_rfc3339(),
+            "2023-04-02T10:00:00+09:00"
+        );
+
+        let (info, _) = take_info("---\ndate: 2023-04-02 10:00\n---\n");
+        assert_eq!(
+            info.published().unwrap().to_rfc3339(),
+            "2023-04-02T10:00:00+02:00"
+        );
+    }
+
+    #[test]
+    fn skipped_time_warning() {
+        let (info, warnings) = take_info("---\ntitle: Spring\nupdated: 2023-03-26 02:30\n---\n");
+        assert_eq!(
+            info.updated.and_then(|it| it.utc()).unwrap().to_rfc3339(),
+            "2023-03-26T01:30:00+00:00"
+        );
+        let [warning @ FormatWarning::SkippedTime { value, used, .. }] = warnings.as_slice() else {
+            panic!("expected a skipped time warning, got {:?}", warnings);
+        };
+        assert_eq!(value, "2023-03-26 02:30");
+        assert_eq!(used, "2023-03-26 03:30:00");
+        assert_eq!(warning.location().line, 3);
+    }
 }
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use console::style;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// How build progress is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Progress bars and a colored summary.
+    Interactive,
+    /// Line per build step and a plain summary.
+    Plain,
+    /// Only the summary.
+    Quiet,
+}
+
+impl OutputMode {
+    /// Uses progress bars only if stdout is a terminal and neither `quiet` nor
+    /// `verbose` output was requested.
+    pub fn detect(quiet: bool, verbose: bool) -> Self {
+        if quiet {
+            OutputMode::Quiet
+        } else if verbose || !console::user_attended() {
+            OutputMode::Plain
+        } else {
+            OutputMode::Interactive
+        }
+    }
+}
+
+/// Progress of a single build step.
+pub struct Progress {
+    bar: ProgressBar,
+}
+
+impl Progress {
+    pub fn new(mode: OutputMode, message: &'static str, len: usize) -> Self {
+        let bar = match mode {
+            OutputMode::Interactive => {
+                let bar = ProgressBar::with_draw_target(
+                    Some(len as u64),
+                    ProgressDrawTarget::stderr(),
+                );
+                let template = "{msg:>10.cyan.bold} [{bar:40}] {pos}/{len} ({elapsed})";
+                if let Ok(it) = ProgressStyle::with_template(template) {
+                    bar.set_style(it.progress_chars("=> "));
+                }
+                bar.set_message(message);
+                bar
+            }
+            OutputMode::Plain => {
+                eprintln!("{} {} post(s)", message, len);
+                ProgressBar::hidden()
+            }
+            OutputMode::Quiet => ProgressBar::hidden(),
+        };
+        Progress { bar }
+    }
+
+    pub fn inc(&self) {
+        self.bar.inc(1);
+    }
+
+    pub fn finish(self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Counts reported at the end of a build.
+#[derive(Debug, Default)]
+pub struct BuildSummary {
+    pub built: usize,
+    /// Posts that aren't published yet.
+    pub skipped: usize,
+    pub failed: usize,
+    pub warnings: usize,
+    /// Files written to the target directory.
+    pub outputs: usize,
+    pub elapsed: Duration,
+}
+
+impl BuildSummary {
+    pub fn print(&self, mode: OutputMode) {
+        let counts = [
+            (self.built, "built", console::Color::Green),
+            (self.skipped, "skipped", console::Color::Cyan),
+            (self.failed, "failed", console::Color::Red),
+            (self.warnings, "warnings", console::Color::Yellow),
+        ];
+        let colored = mode == OutputMode::Interactive;
+
+        let mut line = String::new();
+        for (count, name, color) in counts {
+            if !line.is_empty() {
+                line.push_str(", ");
+            }
+            let text = format!("{} {}", count, name);
+            if colored && count > 0 {
+                line.push_str(&style(text).fg(color).bold().to_string());
+            } else {
+                line.push_str(&text);
+            }
+        }
+
+        let status = match (self.failed, colored) {
+            (0, true) => style("Finished").green().bold().to_string(),
+            (_, true) => style("Finished").red().bold().to_string(),
+            (_, false) => "Finished".to_string(),
+        };
+        eprintln!(
+            "{} {}; wrote {} file(s) in {:.2?}",
+            status, line, self.outputs, self.elapsed
+        );
+    }
+}
+
+/// Counts reported after updating the file index.
+#[derive(Debug, Default)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub elapsed: Duration,
+}
+
+impl IndexSummary {
+    pub fn print(&self, mode: OutputMode) {
+        let status = match mode {
+            OutputMode::Interactive => style("Finished").green().bold().to_string(),
+            _ => "Finished".to_string(),
+        };
+        eprintln!(
+            "{} indexed {} file(s) in {:.2?}",
+            status, self.indexed, self.elapsed
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use git2::{Delta, DiffFindOptions, Oid, Repository, Sort};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::FormatError,
+    frontmatter::{self, Frontmatter},
+    permalink::Permalink,
+    post::PostInfo,
+    slug,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerRedirects {
+    /// `_redirects` file supported by Netlify and Cloudflare Pages.
+    Netlify,
+    /// `redirects.map` file with entries for nginx `map` directive.
+    Nginx,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedirectOptions {
+    /// Add aliases for previous post slugs found in git history.
+    pub history: bool,
+    /// Server side redirect files written in addition to redirect pages.
+    pub server: Vec<ServerRedirects>,
+}
+
+impl Default for RedirectOptions {
+    fn default() -> Self {
+        RedirectOptions {
+            history: true,
+            server: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    pub from: Permalink,
+    /// Root relative URL of the redirect target.
+    pub to: String,
+}
+
+/// Returns slug a post had in some version of its source, if it can be
+/// determined.
+fn historical_slug(source: &str, path: &Path) -> Option<String> {
+    let info = match Frontmatter::find(frontmatter::strip_bom(source)) {
+        Ok(Some(it)) => it.parse::<PostInfo>(path).ok()?.0,
+        Ok(None) => PostInfo::default(),
+        Err(_) => return None,
+    };
+    slug::for_post(&info, path).ok()
+}
+
+/// Path of the slug history cache, relative to blog root.
+pub const HISTORY_CACHE: &str = ".cache/slug-history.json";
+
+/// Slugs found in git history, kept between builds so only commits added
+/// since the last one have to be walked.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SlugHistory {
+    /// Commit the history was collected at.
+    head: String,
+    /// Repository relative source path at `head` -> its slugs up to `head`.
+    slugs: HashMap<PathBuf, Vec<String>>,
+}
+
+impl SlugHistory {
+    fn load(path: &Path) -> Option<SlugHistory> {
+        let content = std::fs::read(path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    fn write(&self, path: &Path) -> Result<(), FormatError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
+
+fn push_slug(slugs: &mut Vec<String>, slug: String) {
+    if !slugs.contains(&slug) {
+        slugs.push(slug);
+    }
+}
+
+/// Walks first parent history from `start` (excluding `hide` and its
+/// ancestors), collecting slugs of files in `names` and following renames.
+///
+/// `names` maps repository relative paths at `start` to source paths; on
+/// return it contains paths the remaining files had at `hide`.
+fn walk_history(
+    repo: &Repository,
+    start: Oid,
+    hide: Option<Oid>,
+    names: &mut HashMap<PathBuf, PathBuf>,
+    result: &mut HashMap<PathBuf, Vec<String>>,
+) -> Result<(), git2::Error> {
+    let mut walk = repo.revwalk()?;
+    walk.push(start)?;
+    if let Some(hide) = hide {
+        walk.hide(hide)?;
+    }
+    walk.simplify_first_parent()?;
+    walk.set_sorting(Sort::TOPOLOGICAL)?;
+
+    for oid in walk {
+        if names.is_empty() {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let parent = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+
+        let mut diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+        for delta in diff.deltas() {
+            let new_path = match delta.new_file().path() {
+                Some(it) => it.to_path_buf(),
+                None => continue,
+            };
+            let source = match names.remove(&new_path) {
+                Some(it) => it,
+                None => continue,
+            };
+
+            let content = repo.find_blob(delta.new_file().id());
+            let slug = content
+                .ok()
+                .and_then(|it| std::str::from_utf8(it.content()).ok().map(str::to_string))
+                .and_then(|it| historical_slug(&it, &new_path));
+            if let Some(slug) = slug {
+                push_slug(result.entry(source.clone()).or_default(), slug);
+            }
+
+            let old_path = match delta.status() {
+                Delta::Added => continue,
+                Delta::Renamed => delta
+                    .old_file()
+                    .path()
+                    .map(Path::to_path_buf)
+                    .unwrap_or(new_path),
+                _ => new_path,
+            };
+            names.insert(old_path, source);
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects slugs `sources` had in previous commits of the git repository
+/// containing `root`, following renames.
+///
+/// Results are cached in `cache` file; if `HEAD` descends from the cached
+/// commit only newer commits are walked.
+///
+/// Returned map contains all distinct slugs found for each source, including
+/// the current one.
+pub fn previous_slugs(
+    root: impl AsRef<Path>,
+    sources: &[PathBuf],
+    cache: impl AsRef<Path>,
+) -> Result<HashMap<PathBuf, Vec<String>>, git2::Error> {
+    let repo = Repository::discover(root)?;
+    let workdir = match repo.workdir().and_then(|it| it.canonicalize().ok()) {
+        Some(it) => it,
+        None => return Ok(HashMap::new()),
+    };
+    let head = repo.head()?.peel_to_commit()?.id();
+
+    // repository relative path at currently walked commit -> source path
+    let current: HashMap<PathBuf, PathBuf> = sources
+        .iter()
+        .filter_map(|source| {
+            let relative = source.canonicalize().ok()?;
+            let relative = relative.strip_prefix(&workdir).ok()?.to_path_buf();
+            Some((relative, source.clone()))
+        })
+        .collect();
+    let mut result: HashMap<PathBuf, Vec<String>> = HashMap::new();
+
+    let cached = SlugHistory::load(cache.as_ref()).and_then(|it| {
+        let oid = Oid::from_str(&it.head).ok()?;
+        let usable = oid == head || repo.graph_descendant_of(head, oid).unwrap_or_default();
+        usable.then_some((oid, it.slugs))
+    });
+
+    let mut names = current.clone();
+    match cached {
+        Some((cached_head, slugs)) => {
+            walk_history(&repo, head, Some(cached_head), &mut names, &mut result)?;
+
+            // files that weren't tracked at the cached commit still need a
+            // full walk
+            let mut missing = HashMap::new();
+            for (path, source) in names {
+                match slugs.get(&path) {
+                    Some(slugs) => {
+                        let found = result.entry(source).or_default();
+                        for slug in slugs {
+                            push_slug(found, slug.clone());
+                        }
+                    }
+                    None => {
+                        missing.insert(path, source);
+                    }
+                }
+            }
+            walk_history(&repo, cached_head, None, &mut missing, &mut result)?;
+        }
+        None => walk_history(&repo, head, None, &mut names, &mut result)?,
+    }
+
+    let history = SlugHistory {
+        head: head.to_string(),
+        slugs: current
+            .into_iter()
+            .map(|(path, source)| (path, result.get(&source).cloned().unwrap_or_default()))
+            .collect(),
+    };
+    if let Err(err) = history.write(cache.as_ref()) {
+        log::debug!("unable to write slug history cache: {}", err);
+    }
+
+    Ok(result)
+}
+
+/// Writes server side redirect configuration files into `target_dir`.
+pub fn write_server_files(
+    redirects: &[Redirect],
+    formats: &[ServerRedirects],
+    target_dir: impl AsRef<Path>,
+) -> Result<(), FormatError> {
+    let target_dir = target_dir.as_ref();
+
+    for format in formats {
+        let mut content = String::with_capacity(redirects.len() * 64);
+        let file_name = match format {
+            ServerRedirects::Netlify => {
+                for it in redirects {
+                    let _ = writeln!(content, "{} {} 301", it.from.url, it.to);
+                }
+                "_redirects"
+            }
+            ServerRedirects::Nginx => {
+                for it in redirects {
+                    let _ = writeln!(content, "\"{}\" \"{}\";", it.from.url, it.to);
+                }
+                "redirects.map"
+            }
+        };
+        std::fs::write(target_dir.join(file_name), content)?;
+    }
+
+    Ok(())
+}
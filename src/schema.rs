@@ -0,0 +1,144 @@
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{FormatError, SourceLocation},
+    frontmatter::Frontmatter,
+};
+
+/// Name of the custom field schema file expected in the blog root.
+pub const SCHEMA_FILE: &str = "schema.yaml";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum FieldType {
+    #[default]
+    Any,
+    String,
+    Number,
+    Integer,
+    Bool,
+    List,
+    Map,
+}
+
+impl FieldType {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            FieldType::Any => true,
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Bool => value.is_boolean(),
+            FieldType::List => value.is_array(),
+            FieldType::Map => value.is_object(),
+        }
+    }
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "map",
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldSchema {
+    #[serde(rename = "type", default)]
+    pub kind: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    /// Value used when the field isn't specified in frontmatter.
+    #[serde(default)]
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Declares custom frontmatter fields which are passed through to templates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrontmatterSchema {
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldSchema>,
+}
+
+impl FrontmatterSchema {
+    /// Loads schema from `schema.yaml` in the provided blog root.
+    pub fn load(root: impl AsRef<Path>) -> Result<Option<FrontmatterSchema>, FormatError> {
+        let path = root.as_ref().join(SCHEMA_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let reader = BufReader::new(File::open(&path)?);
+        let schema: FrontmatterSchema =
+            serde_yaml::from_reader(reader).map_err(|err| FormatError::yaml(&path, 0, err))?;
+
+        for (name, field) in &schema.fields {
+            if let Some(default) = &field.default {
+                if !field.kind.matches(default) {
+                    return Err(FormatError::FieldType {
+                        location: SourceLocation::new(&path, 1, 1),
+                        field: name.clone(),
+                        expected: field.kind,
+                        found: value_kind(default),
+                    });
+                }
+            }
+        }
+
+        Ok(Some(schema))
+    }
+
+    pub fn declares(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    /// Validates custom fields of a post and inserts defaults for missing
+    /// ones.
+    pub fn apply(
+        &self,
+        extra: &mut Map<String, Value>,
+        path: impl AsRef<Path>,
+        frontmatter: Option<&Frontmatter>,
+    ) -> Result<(), FormatError> {
+        let path = path.as_ref();
+
+        for (name, field) in &self.fields {
+            let value = match extra.get(name) {
+                Some(it) => it,
+                None => {
+                    if let Some(default) = &field.default {
+                        extra.insert(name.clone(), default.clone());
+                    } else if field.required {
+                        return Err(FormatError::MissingField {
+                            path: path.to_path_buf(),
+                            field: name.clone(),
+                        });
+                    }
+                    continue;
+                }
+            };
+
+            if !field.kind.matches(value) {
+                let line = frontmatter.map(|it| it.key_line(name)).unwrap_or(1);
+                return Err(FormatError::FieldType {
+                    location: SourceLocation::new(path, line, 1),
+                    field: name.clone(),
+                    expected: field.kind,
+                    found: value_kind(value),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
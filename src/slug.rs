@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use crate::{error::FormatError, post::PostInfo};
+
+/// Converts arbitrary text into a slug.
+///
+/// Text is transliterated to ASCII (e.g. "Švagelj" becomes "svagelj"),
+/// lowercased, and runs of other characters are replaced with a single `-`.
+pub fn slugify(text: impl AsRef<str>) -> String {
+    let ascii = deunicode::deunicode(text.as_ref());
+
+    let mut result = String::with_capacity(ascii.len());
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            result.push(c.to_ascii_lowercase());
+        } else if c == '_' {
+            result.push(c);
+        } else if !result.is_empty() && !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+
+    while result.ends_with('-') {
+        result.pop();
+    }
+    result
+}
+
+/// Returns true if `slug` only contains characters that are safe to use in
+/// file names and URLs without escaping: lowercase ASCII letters, digits, `-`
+/// and `_`.
+pub fn is_valid(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Derives a slug from source file path.
+///
+/// For `index.md` files name of the containing directory is used instead.
+pub fn from_path(path: impl AsRef<Path>) -> String {
+    let path = path.as_ref();
+    let name = match path.file_name().and_then(|it| it.to_str()) {
+        Some(it) => it.split('.').next().unwrap_or(it),
+        None => return String::new(),
+    };
+
+    if name == "index" {
+        if let Some(parent) = path.parent().and_then(|it| it.file_name()) {
+            return slugify(parent.to_string_lossy());
+        }
+    }
+    slugify(name)
+}
+
+/// Returns slug of a post with `info` read from `path`.
+///
+/// Slug is taken from frontmatter, or derived from title or source path.
+pub fn for_post(info: &PostInfo, path: impl AsRef<Path>) -> Result<String, FormatError> {
+    let path = path.as_ref();
+
+    if let Some(slug) = &info.slug {
+        if !is_valid(slug) {
+            return Err(FormatError::InvalidSlug {
+                path: path.to_path_buf(),
+                slug: slug.clone(),
+            });
+        }
+        return Ok(slug.clone());
+    }
+
+    let derived = info
+        .title
+        .as_ref()
+        .map(slugify)
+        .filter(|it| !it.is_empty())
+        .unwrap_or_else(|| from_path(path));
+
+    if derived.is_empty() {
+        Ok("output".to_string())
+    } else {
+        Ok(derived)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(title: Option<&str>, slug: Option<&str>) -> PostInfo {
+        PostInfo {
+            title: title.map(str::to_string),
+            slug: slug.map(str::to_string),
+            ..PostInfo::default()
+        }
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  --Rust__2024--  "), "rust__2024");
+        assert_eq!(slugify("Švagelj čaj"), "svagelj-caj");
+        assert_eq!(slugify("Größe"), "grosse");
+        assert_eq!(slugify("?!"), "");
+    }
+
+    #[test]
+    fn transliteration_collisions() {
+        // different titles can produce the same slug, which the build reports
+        // as an output collision
+        assert_eq!(slugify("Čaj"), slugify("Caj"));
+        assert_eq!(slugify("C++ & C#"), slugify("C C"));
+        assert_eq!(
+            for_post(&info(Some("Résumé"), None), "a.md").unwrap(),
+            for_post(&info(Some("resume"), None), "b.md").unwrap()
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert!(is_valid("hello-world_2"));
+        assert!(!is_valid(""));
+        assert!(!is_valid("Hello"));
+        assert!(!is_valid("a/b"));
+        assert!(!is_valid("../a"));
+        assert!(!is_valid("čaj"));
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(from_path("posts/My Post.md"), "my-post");
+        assert_eq!(from_path("posts/archive.tar.md"), "archive");
+        assert_eq!(from_path("posts/Über uns/index.md"), "uber-uns");
+        assert_eq!(from_path("index.md"), "index");
+    }
+
+    #[test]
+    fn post_slugs() {
+        assert_eq!(
+            for_post(&info(Some("Title"), Some("custom")), "a.md").unwrap(),
+            "custom"
+        );
+        assert!(for_post(&info(None, Some("Not Valid")), "a.md").is_err());
+        assert_eq!(
+            for_post(&info(Some("Title"), None), "a.md").unwrap(),
+            "title"
+        );
+        assert_eq!(
+            for_post(&info(Some("!!"), None), "file.md").unwrap(),
+            "file"
+        );
+        assert_eq!(for_post(&info(None, None), "").unwrap(), "output");
+    }
+}
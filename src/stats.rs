@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::component::{LinkKind, PostComponent, Style, TextComponent, TextPart};
+
+/// Reading speed for alphabetic scripts, in words per minute.
+const WORDS_PER_MINUTE: usize = 200;
+/// Reading speed for CJK scripts, in characters per minute.
+const CJK_CHARS_PER_MINUTE: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// Anchor of the heading.
+    pub id: Option<String>,
+}
+
+/// Content statistics of a post.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostStats {
+    /// Number of words, excluding code. Each CJK character counts as a word.
+    pub words: usize,
+    /// Number of characters excluding whitespace and code.
+    pub characters: usize,
+    /// Estimated reading time in minutes.
+    pub reading_time: usize,
+    pub images: usize,
+    pub code_blocks: usize,
+    pub links: usize,
+    /// Headings in document order.
+    pub outline: Vec<Heading>,
+    #[serde(skip)]
+    cjk_characters: usize,
+}
+
+/// Returns true for characters of scripts that don't separate words with
+/// spaces.
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0xFF66..=0xFF9F // Halfwidth Katakana
+        | 0x20000..=0x2FA1F // CJK Extensions B-F, Compatibility Supplement
+    )
+}
+
+impl PostStats {
+    pub fn new(components: &[PostComponent]) -> PostStats {
+        let mut result = PostStats::default();
+        for it in components {
+            result.visit(it);
+        }
+
+        let latin_words = result.words - result.cjk_characters;
+        let minutes = latin_words as f64 / WORDS_PER_MINUTE as f64
+            + result.cjk_characters as f64 / CJK_CHARS_PER_MINUTE as f64;
+        result.reading_time = match result.words {
+            0 => 0,
+            _ => (minutes.ceil() as usize).max(1),
+        };
+
+        result
+    }
+
+    fn count_text(&mut self, text: &str) {
+        let mut in_word = false;
+        for c in text.chars() {
+            if c.is_whitespace() {
+                in_word = false;
+                continue;
+            }
+            self.characters += 1;
+
+            if is_cjk(c) {
+                self.words += 1;
+                self.cjk_characters += 1;
+                in_word = false;
+            } else if !in_word && c.is_alphanumeric() {
+                self.words += 1;
+                in_word = true;
+            }
+        }
+    }
+
+    fn visit_text(&mut self, text: &TextComponent) {
+        match &text.style {
+            Style::Link(link) if link.kind != LinkKind::Footnote => self.links += 1,
+            Style::Heading { level, id } => self.outline.push(Heading {
+                level: *level,
+                text: text.plain_text().trim().to_string(),
+                id: id.as_ref().map(|it| it.to_string()),
+            }),
+            _ => {}
+        }
+        self.visit_part(&text.content);
+    }
+
+    fn visit_part(&mut self, part: &TextPart) {
+        match part {
+            TextPart::Raw(content) => self.count_text(content),
+            TextPart::Chained(items) => {
+                for it in items {
+                    self.visit_part(it);
+                }
+            }
+            TextPart::Nested(inner) => self.visit_text(inner),
+            TextPart::Empty | TextPart::NewLine => {}
+        }
+    }
+
+    fn visit(&mut self, component: &PostComponent) {
+        match component {
+            PostComponent::Text(text) => self.visit_text(text),
+            PostComponent::BlockQuote(items) | PostComponent::Chained(items) => {
+                for it in items {
+                    self.visit(it);
+                }
+            }
+            PostComponent::Admonition(admonition) => {
+                // default titles aren't part of the written text
+                if admonition.custom_title {
+                    self.visit_text(&admonition.title);
+                }
+                for it in &admonition.content {
+                    self.visit(it);
+                }
+            }
+            PostComponent::Image { .. } => self.images += 1,
+            PostComponent::CodeBlock { .. } => self.code_blocks += 1,
+            PostComponent::List(list) => {
+                for it in &list.items {
+                    self.visit(it);
+                }
+            }
+            PostComponent::Table(table) => {
+                for cell in table.cells() {
+                    self.visit(cell);
+                }
+            }
+            PostComponent::Footnote { text, .. } => self.visit_text(text),
+            PostComponent::Placeholder
+            | PostComponent::HorizonalRule
+            | PostComponent::Latex(_)
+            | PostComponent::Raw(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::parser::ComponentParser;
+
+    fn stats(source: &str) -> PostStats {
+        let components: Vec<_> = ComponentParser::new(source).collect();
+        PostStats::new(&components)
+    }
+
+    #[test]
+    fn words() {
+        let stats = stats("# Title\n\nOne two, three-four.\n\n```\nnot counted\n```\n");
+        assert_eq!(stats.words, 4);
+        assert_eq!(stats.code_blocks, 1);
+        assert_eq!(stats.reading_time, 1);
+    }
+
+    #[test]
+    fn cjk_characters() {
+        assert_eq!(stats("日本語 text").words, 4);
+    }
+
+    #[test]
+    fn admonition_titles() {
+        assert_eq!(stats("> [!NOTE]\n> One two\n").words, 2);
+        assert_eq!(stats("> [!NOTE] Custom title\n> One two\n").words, 4);
+    }
+}
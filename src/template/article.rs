@@ -1,5 +1,6 @@
 use std::path::Path;
 
+use handlebars::Handlebars;
 use serde::Serialize;
 
 use crate::error::FormatError;
@@ -14,8 +15,7 @@ pub struct RedirectTemplate {
 }
 
 impl Generate for RedirectTemplate {
-    fn generate(&self, path: impl AsRef<Path>) -> Result<String, FormatError> {
-        let engine = super::engine().read().expect("engine poisoned");
+    fn generate(&self, engine: &Handlebars, path: impl AsRef<Path>) -> Result<String, FormatError> {
         let contents = engine.render("redirect", &self)?;
         if let Some(parent) = path.as_ref().parent() {
             std::fs::create_dir_all(parent)?;
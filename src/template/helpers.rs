@@ -0,0 +1,432 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::{Mutex, RwLock},
+};
+
+use chrono::Locale;
+use chrono_tz::Tz;
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, JsonValue, RenderContext,
+    RenderError, RenderErrorReason, ScopedJson,
+};
+
+use crate::{asset, date, slug, util};
+
+/// Default format used by the `date` helper.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Default URL pattern of tag pages, used by the `url_for` helper.
+pub const DEFAULT_TAG_URL: &str = "/tags/:tag/";
+
+/// Build state helpers depend on, filled in before rendering.
+#[derive(Debug, Default)]
+pub struct HelperData {
+    /// Blog root; `asset` paths are relative to it.
+    pub root: PathBuf,
+    /// Timezone dates are displayed in, unless overridden with `tz=`.
+    pub timezone: Option<Tz>,
+    /// Tag page URL pattern with a `:tag` placeholder.
+    pub tag_url: Option<String>,
+    /// Post slug -> post URL.
+    pub posts: HashMap<String, String>,
+    /// Root relative path -> fingerprinted path of files used with `asset`,
+    /// copied to the target directory after rendering.
+    pub assets: Mutex<HashMap<PathBuf, PathBuf>>,
+}
+
+lazy_static::lazy_static! {
+    static ref HELPER_DATA: RwLock<HelperData> = RwLock::new(HelperData::default());
+}
+
+pub fn data() -> &'static RwLock<HelperData> {
+    &HELPER_DATA
+}
+
+fn other(message: impl ToString) -> RenderError {
+    RenderErrorReason::Other(message.to_string()).into()
+}
+
+fn derived<'rc>(value: impl Into<JsonValue>) -> Result<ScopedJson<'rc>, RenderError> {
+    Ok(ScopedJson::Derived(value.into()))
+}
+
+fn param_str<'a>(h: &'a Helper, index: usize, helper: &'static str) -> Result<&'a str, RenderError> {
+    match h.param(index).map(|it| it.value()) {
+        Some(JsonValue::String(it)) => Ok(it),
+        Some(_) => Err(RenderErrorReason::InvalidParamType("string").into()),
+        None => Err(RenderErrorReason::ParamNotFoundForIndex(helper, index).into()),
+    }
+}
+
+fn hash_str<'a>(h: &'a Helper, key: &str) -> Option<&'a str> {
+    h.hash_get(key).and_then(|it| it.value().as_str())
+}
+
+/// Removes HTML tags from `html` and decodes basic entities.
+pub fn strip_tags(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                result.push(' ');
+            }
+            _ if !in_tag => result.push(c),
+            _ => {}
+        }
+    }
+    result
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Shortens `text` to at most `length` characters, ending it with `…` if
+/// anything was removed.
+fn truncate_chars(text: &str, length: usize) -> String {
+    match text.char_indices().nth(length) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// Formats a date string (as serialized in post info) with a `strftime`
+/// format string.
+///
+/// `{{date updated "%e %B %Y" tz="Europe/Zagreb" locale="hr_HR"}}`
+pub struct DateHelper;
+
+impl HelperDef for DateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = match h.param(0).map(|it| it.value()) {
+            Some(JsonValue::String(it)) => it,
+            Some(JsonValue::Null) | None => return derived(JsonValue::Null),
+            Some(_) => return Err(RenderErrorReason::InvalidParamType("string").into()),
+        };
+        let format = match h.param(1) {
+            Some(_) => param_str(h, 1, "date")?,
+            None => DEFAULT_DATE_FORMAT,
+        };
+
+        let tz = match hash_str(h, "tz") {
+            Some(it) => Tz::from_str(it).map_err(|_| other(format!("unknown timezone: {}", it)))?,
+            None => data().read().expect("helper data poisoned").timezone.unwrap_or(Tz::UTC),
+        };
+        let date = date::parse_date(value, tz)
+            .map_err(other)?
+            .with_timezone(&tz);
+
+        let mut result = String::new();
+        let written = match hash_str(h, "locale") {
+            Some(it) => {
+                let locale =
+                    Locale::from_str(it).map_err(|_| other(format!("unknown locale: {}", it)))?;
+                write!(result, "{}", date.format_localized(format, locale))
+            }
+            None => write!(result, "{}", date.format(format)),
+        };
+        written.map_err(|_| other(format!("invalid date format: {}", format)))?;
+
+        derived(result)
+    }
+}
+
+/// Returns root relative URL of a post by its slug, or of a tag page.
+///
+/// `{{url_for "some-post"}}`, `{{url_for tag="Rust"}}`
+pub struct UrlForHelper;
+
+impl HelperDef for UrlForHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let data = data().read().expect("helper data poisoned");
+
+        if let Some(tag) = hash_str(h, "tag") {
+            let pattern = data.tag_url.as_deref().unwrap_or(DEFAULT_TAG_URL);
+            return derived(pattern.replace(":tag", &slug::slugify(tag)));
+        }
+
+        let slug = param_str(h, 0, "url_for")?;
+        match data.posts.get(slug) {
+            Some(url) => derived(url.clone()),
+            None => Err(other(format!("url_for: no post with slug '{}'", slug))),
+        }
+    }
+}
+
+/// Returns root relative URL of a fingerprinted copy of a file in the blog
+/// root, for cache busting.
+///
+/// `{{asset "static/style.css"}}` renders as `/static/style.<hash>.css`.
+pub struct AssetHelper;
+
+impl HelperDef for AssetHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let path = param_str(h, 0, "asset")?;
+        let relative = Path::new(path.trim_start_matches('/'));
+        if relative
+            .components()
+            .any(|it| !matches!(it, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(other(format!("asset: path '{}' leaves blog root", path)));
+        }
+
+        let relative = asset::normalize(relative);
+        let data = data().read().expect("helper data poisoned");
+        let full = data.root.join(&relative);
+        let content = std::fs::read(&full)
+            .map_err(|err| other(format!("asset: can't read {}: {}", full.display(), err)))?;
+
+        let fingerprinted = asset::fingerprinted(&relative, &util::content_hash(&content));
+        let url = asset::url(&fingerprinted);
+        data.assets
+            .lock()
+            .expect("helper data poisoned")
+            .insert(relative, fingerprinted);
+        derived(url)
+    }
+}
+
+handlebars_helper!(slugify: |text: str| slug::slugify(text));
+
+handlebars_helper!(author_names: |author: Json| {
+    fn name(entry: &JsonValue) -> Option<&str> {
+        match entry {
+            JsonValue::String(it) => Some(it),
+            JsonValue::Object(it) => it.get("name").and_then(JsonValue::as_str),
+            _ => None,
+        }
+    }
+    match author {
+        JsonValue::Array(list) => list.iter().filter_map(name).collect::<Vec<_>>().join(", "),
+        other => name(other).unwrap_or_default().to_string(),
+    }
+});
+
+handlebars_helper!(reading_time: |content: str, { wpm: u64 = 200 }| {
+    let words = strip_tags(content).split_whitespace().count() as u64;
+    let wpm = wpm.max(1);
+    words.div_ceil(wpm).max(1)
+});
+
+handlebars_helper!(truncate: |text: str, length: u64| truncate_chars(text, length as usize));
+
+handlebars_helper!(excerpt: |content: str, { words: u64 = 50 }| {
+    let text = strip_tags(content);
+    let mut iter = text.split_whitespace();
+    let mut result = iter.by_ref().take(words as usize).collect::<Vec<_>>().join(" ");
+    if iter.next().is_some() {
+        result.push('…');
+    }
+    result
+});
+
+handlebars_helper!(json: |value: Json, { pretty: bool = false }| {
+    let result = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    // allows embedding the output in <script> elements
+    result.unwrap_or_default().replace("</", "<\\/")
+});
+
+handlebars_helper!(default: |value: Json, fallback: Json| {
+    match value {
+        JsonValue::Null => fallback.clone(),
+        JsonValue::String(it) if it.is_empty() => fallback.clone(),
+        other => other.clone(),
+    }
+});
+
+handlebars_helper!(contains: |collection: Json, item: Json| {
+    match collection {
+        JsonValue::Array(list) => list.contains(item),
+        JsonValue::String(text) => item.as_str().map(|it| text.contains(it)).unwrap_or_default(),
+        JsonValue::Object(map) => item.as_str().map(|it| map.contains_key(it)).unwrap_or_default(),
+        _ => false,
+    }
+});
+
+/// Registers built-in helpers with the template engine.
+///
+/// Comparison and logic helpers (`eq`, `ne`, `gt`, `gte`, `lt`, `lte`, `and`,
+/// `or`, `not`, `len`) are provided by handlebars itself.
+pub fn register(handlebars: &mut Handlebars) {
+    handlebars.register_helper("date", Box::new(DateHelper));
+    handlebars.register_helper("url_for", Box::new(UrlForHelper));
+    handlebars.register_helper("asset", Box::new(AssetHelper));
+    handlebars.register_helper("slugify", Box::new(slugify));
+    handlebars.register_helper("author_names", Box::new(author_names));
+    handlebars.register_helper("reading_time", Box::new(reading_time));
+    handlebars.register_helper("truncate", Box::new(truncate));
+    handlebars.register_helper("excerpt", Box::new(excerpt));
+    handlebars.register_helper("json", Box::new(json));
+    handlebars.register_helper("default", Box::new(default));
+    handlebars.register_helper("contains", Box::new(contains));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard};
+
+    use serde_json::json;
+
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    /// Resets the helper data shared by all tests and holds it until the
+    /// returned guard is dropped, so tests running in parallel don't mix.
+    fn reset() -> MutexGuard<'static, ()> {
+        let guard = LOCK.lock().unwrap_or_else(|it| it.into_inner());
+        *data().write().unwrap() = HelperData::default();
+        guard
+    }
+
+    fn render(template: &str, context: JsonValue) -> String {
+        let mut handlebars = Handlebars::new();
+        register(&mut handlebars);
+        handlebars
+            .render_template(template, &context)
+            .expect("unable to render template")
+    }
+
+    #[test]
+    fn date() {
+        let _guard = reset();
+        let context = json!({ "updated": "2024-02-01T10:00:00Z" });
+        assert_eq!(render("{{date updated}}", context.clone()), "2024-02-01");
+        assert_eq!(
+            render(
+                r#"{{date updated "%d %B %Y %H:%M" tz="Europe/Berlin" locale="de_DE"}}"#,
+                context
+            ),
+            "01 Februar 2024 11:00"
+        );
+    }
+
+    #[test]
+    fn url_for() {
+        let _guard = reset();
+        {
+            let mut data = data().write().unwrap();
+            data.posts.insert("some-post".to_string(), "/some-post.html".to_string());
+        }
+        assert_eq!(render(r#"{{url_for "some-post"}}"#, json!({})), "/some-post.html");
+    }
+
+    #[test]
+    fn url_for_tag() {
+        let _guard = reset();
+        data().write().unwrap().tag_url = Some("/topics/:tag.html".to_string());
+        assert_eq!(
+            render(r#"{{url_for tag="Rust Lang"}}"#, json!({})),
+            "/topics/rust-lang.html"
+        );
+    }
+
+    #[test]
+    fn asset() {
+        let _guard = reset();
+        let root = std::env::temp_dir().join(format!("blog-parser-asset-{}", util::random_id()));
+        std::fs::create_dir_all(root.join("static")).unwrap();
+        std::fs::write(root.join("static/style.css"), "body {}").unwrap();
+        data().write().unwrap().root = root.clone();
+
+        let hash = util::content_hash(b"body {}");
+        assert_eq!(
+            render(r#"{{asset "./static/style.css"}}"#, json!({})),
+            format!("/static/style.{}.css", hash)
+        );
+        assert_eq!(
+            data().read().unwrap().assets.lock().unwrap().get(Path::new("static/style.css")),
+            Some(&PathBuf::from(format!("static/style.{}.css", hash)))
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn slugify() {
+        let _guard = reset();
+        assert_eq!(render("{{slugify title}}", json!({ "title": "Hello, World!" })), "hello-world");
+    }
+
+    #[test]
+    fn author_names() {
+        let _guard = reset();
+        let context = json!({ "author": ["Ann", { "name": "Bob", "email": "bob@example.com" }] });
+        assert_eq!(render("{{author_names author}}", context), "Ann, Bob");
+    }
+
+    #[test]
+    fn reading_time() {
+        let _guard = reset();
+        let context = json!({ "content": format!("<p>{}</p>", "word ".repeat(450)) });
+        assert_eq!(render("{{reading_time content}}", context.clone()), "3");
+        assert_eq!(render("{{reading_time content wpm=500}}", context), "1");
+    }
+
+    #[test]
+    fn truncate() {
+        let _guard = reset();
+        let context = json!({ "title": "A rather long title" });
+        assert_eq!(render("{{truncate title 8}}", context.clone()), "A rather…");
+        assert_eq!(render("{{truncate title 40}}", context), "A rather long title");
+    }
+
+    #[test]
+    fn excerpt() {
+        let _guard = reset();
+        let context = json!({ "content": "<p>One <em>two</em> three four</p>" });
+        assert_eq!(render("{{excerpt content words=2}}", context), "One two…");
+    }
+
+    #[test]
+    fn json() {
+        let _guard = reset();
+        let context = json!({ "value": { "html": "</script>" } });
+        assert_eq!(render("{{{json value}}}", context), r#"{"html":"<\/script>"}"#);
+    }
+
+    #[test]
+    fn default() {
+        let _guard = reset();
+        let template = "{{default description title}}";
+        assert_eq!(render(template, json!({ "description": "", "title": "Title" })), "Title");
+        assert_eq!(render(template, json!({ "description": "Text", "title": "Title" })), "Text");
+    }
+
+    #[test]
+    fn contains() {
+        let _guard = reset();
+        let template = r#"{{#if (contains tags "rust")}}yes{{else}}no{{/if}}"#;
+        assert_eq!(render(template, json!({ "tags": ["rust", "blog"] })), "yes");
+        assert_eq!(render(template, json!({ "tags": ["blog"] })), "no");
+    }
+}
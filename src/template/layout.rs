@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use handlebars::Handlebars;
+use serde::Serialize;
+
+use crate::{
+    config::{BlogConfig, SiteConfig},
+    error::FormatError,
+    post::PostTemplateContext,
+};
+
+use super::meta::PageMeta;
+
+#[derive(Debug, Serialize)]
+pub struct FeedLink {
+    pub url: String,
+    /// MIME type of the feed, derived from the URL.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+impl FeedLink {
+    pub fn new(url: impl ToString) -> FeedLink {
+        let url = url.to_string();
+        let kind = if url.ends_with(".json") {
+            "application/feed+json"
+        } else if url.ends_with(".atom") || url.contains("atom") {
+            "application/atom+xml"
+        } else {
+            "application/rss+xml"
+        };
+        FeedLink { url, kind }
+    }
+}
+
+/// Data layout templates are rendered with.
+#[derive(Debug, Serialize)]
+pub struct LayoutContext<'a> {
+    pub site: &'a SiteConfig,
+    pub post: &'a PostTemplateContext,
+    pub lang: &'a str,
+    /// Page title; post title followed by site title.
+    pub title: String,
+    pub description: Option<&'a str>,
+    /// Absolute URL of the post if site URL is configured, root relative
+    /// otherwise.
+    pub canonical: Option<String>,
+    pub feed: Option<FeedLink>,
+    /// OpenGraph, Twitter card and JSON-LD metadata.
+    pub meta: PageMeta,
+    /// Rendered article.
+    pub content: String,
+}
+
+impl<'a> LayoutContext<'a> {
+    pub fn new(
+        site: &'a SiteConfig,
+        post: &'a PostTemplateContext,
+        modified: Option<DateTime<Utc>>,
+        content: String,
+    ) -> Self {
+        let title = match (&post.info.title, &site.title) {
+            (Some(post), Some(site)) if post != site => format!("{} | {}", post, site),
+            (Some(it), _) | (None, Some(it)) => it.clone(),
+            (None, None) => String::new(),
+        };
+        let canonical = post
+            .url
+            .as_ref()
+            .map(|url| site.absolute_url(url).unwrap_or_else(|| url.clone()));
+        let meta = PageMeta::new(site, post, canonical.as_deref(), modified);
+
+        LayoutContext {
+            site,
+            post,
+            lang: post
+                .info
+                .lang
+                .as_deref()
+                .or(site.lang.as_deref())
+                .unwrap_or("en"),
+            title,
+            description: post
+                .info
+                .description
+                .as_deref()
+                .or(site.description.as_deref()),
+            canonical,
+            feed: site.feed.as_ref().map(FeedLink::new),
+            meta,
+            content,
+        }
+    }
+}
+
+/// Renders a post with the `article` template and wraps it in the layout
+/// selected for it.
+///
+/// `modified` is source modification time, used in page metadata if the post
+/// doesn't specify one.
+pub fn render_page(
+    reg: &Handlebars,
+    config: &BlogConfig,
+    post: &PostTemplateContext,
+    modified: Option<DateTime<Utc>>,
+) -> Result<String, FormatError> {
+    let article = reg.render("article", post)?;
+
+    match config.layout(&post.info) {
+        Some(layout) => {
+            let ctx = LayoutContext::new(&config.site, post, modified, article);
+            Ok(reg.render(layout, &ctx)?)
+        }
+        None => Ok(article),
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use handlebars::html_escape;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{config::SiteConfig, date::PostDate, post::PostTemplateContext};
+
+/// Link preview and search engine metadata of a post page, ready to be
+/// embedded in `<head>` with triple braces (`{{{meta.social}}}`).
+#[derive(Debug, Default, Serialize)]
+pub struct PageMeta {
+    /// OpenGraph and Twitter card `<meta>` tags.
+    pub social: String,
+    /// schema.org `BlogPosting` in a JSON-LD `<script>` element.
+    pub json_ld: String,
+    /// Structured data included in `json_ld`, for custom use.
+    pub structured_data: Value,
+}
+
+fn meta_tag(out: &mut String, attribute: &str, name: &str, content: &str) {
+    let _ = writeln!(
+        out,
+        "<meta {}=\"{}\" content=\"{}\" />",
+        attribute,
+        name,
+        html_escape(content)
+    );
+}
+
+/// Serializes `value` as JSON that can't terminate a `<script>` element.
+pub fn script_json(value: &Value) -> String {
+    serde_json::to_string(value)
+        .unwrap_or_default()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+impl PageMeta {
+    /// Builds page metadata for a post.
+    ///
+    /// `modified` is used as modification date if the post doesn't have an
+    /// `updated` date or edits, usually from file metadata.
+    pub fn new(
+        site: &SiteConfig,
+        post: &PostTemplateContext,
+        canonical: Option<&str>,
+        modified: Option<DateTime<Utc>>,
+    ) -> PageMeta {
+        let info = &post.info;
+        let absolute = |url: &str| {
+            if url.starts_with('/') {
+                site.absolute_url(url).unwrap_or_else(|| url.to_string())
+            } else {
+                url.to_string()
+            }
+        };
+
+        let title = info.title.as_deref().or(site.title.as_deref());
+        let description = info.description.as_deref().or(site.description.as_deref());
+        let image = info.image.as_deref().or(site.image.as_deref()).map(absolute);
+        let authors = info.author.as_ref().map(|it| it.authors()).unwrap_or_default();
+
+        let published = info.published();
+        let modified = match &info.updated {
+            Some(PostDate::Resolved(it)) => Some(*it),
+            _ => info
+                .edits
+                .as_ref()
+                .and_then(|it| it.last())
+                .map(|it| it.time)
+                .or(modified)
+                .map(|it| it.fixed_offset()),
+        }
+        .or(published);
+        let published = published.map(|it| it.to_rfc3339_opts(SecondsFormat::Secs, true));
+        let modified = modified.map(|it| it.to_rfc3339_opts(SecondsFormat::Secs, true));
+
+        let mut social = String::with_capacity(1024);
+        meta_tag(&mut social, "property", "og:type", "article");
+        if let Some(it) = title {
+            meta_tag(&mut social, "property", "og:title", it);
+        }
+        if let Some(it) = description {
+            meta_tag(&mut social, "property", "og:description", it);
+        }
+        if let Some(it) = canonical {
+            meta_tag(&mut social, "property", "og:url", it);
+        }
+        if let Some(it) = &site.title {
+            meta_tag(&mut social, "property", "og:site_name", it);
+        }
+        if let Some(it) = &image {
+            meta_tag(&mut social, "property", "og:image", it);
+        }
+        if let Some(it) = &published {
+            meta_tag(&mut social, "property", "article:published_time", it);
+        }
+        if let Some(it) = &modified {
+            meta_tag(&mut social, "property", "article:modified_time", it);
+        }
+        for author in &authors {
+            meta_tag(&mut social, "property", "article:author", &author.name);
+        }
+        for tag in &info.tags {
+            meta_tag(&mut social, "property", "article:tag", tag);
+        }
+
+        let card = match image {
+            Some(_) => "summary_large_image",
+            None => "summary",
+        };
+        meta_tag(&mut social, "name", "twitter:card", card);
+        if let Some(it) = &site.twitter {
+            meta_tag(&mut social, "name", "twitter:site", it);
+        }
+        if let Some(it) = title {
+            meta_tag(&mut social, "name", "twitter:title", it);
+        }
+        if let Some(it) = description {
+            meta_tag(&mut social, "name", "twitter:description", it);
+        }
+        if let Some(it) = &image {
+            meta_tag(&mut social, "name", "twitter:image", it);
+        }
+
+        let mut data = Map::new();
+        data.insert("@context".to_string(), json!("https://schema.org"));
+        data.insert("@type".to_string(), json!("BlogPosting"));
+        let mut insert = |key: &str, value: Option<Value>| {
+            if let Some(value) = value {
+                data.insert(key.to_string(), value);
+            }
+        };
+        insert("headline", title.map(Value::from));
+        insert("description", description.map(Value::from));
+        insert("url", canonical.map(Value::from));
+        insert(
+            "mainEntityOfPage",
+            canonical.map(|it| json!({ "@type": "WebPage", "@id": it })),
+        );
+        insert("image", image.clone().map(Value::from));
+        insert("datePublished", published.map(Value::from));
+        insert("dateModified", modified.map(Value::from));
+        insert("inLanguage", info.lang.as_deref().or(site.lang.as_deref()).map(Value::from));
+        if !authors.is_empty() {
+            let authors = authors
+                .iter()
+                .map(|author| {
+                    let mut person = json!({ "@type": "Person", "name": author.name });
+                    if let Some(it) = &author.web {
+                        person["url"] = json!(it);
+                    }
+                    if let Some(it) = &author.email {
+                        person["email"] = json!(it);
+                    }
+                    person
+                })
+                .collect();
+            insert("author", Some(Value::Array(authors)));
+        }
+        if !info.tags.is_empty() {
+            insert("keywords", Some(Value::from(info.tags.join(", "))));
+        }
+        insert(
+            "publisher",
+            site.title.as_ref().map(|it| json!({ "@type": "Organization", "name": it })),
+        );
+        let structured_data = Value::Object(data);
+
+        PageMeta {
+            social,
+            json_ld: format!(
+                "<script type=\"application/ld+json\">{}</script>",
+                script_json(&structured_data)
+            ),
+            structured_data,
+        }
+    }
+}
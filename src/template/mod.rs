@@ -1,4 +1,5 @@
 use handlebars::{Handlebars, Template};
+use nym::glob::Glob;
 use std::{
     mem::MaybeUninit,
     path::Path,
@@ -8,13 +9,19 @@ use std::{
 use crate::error::FormatError;
 
 pub mod article;
+pub mod helpers;
+pub mod layout;
+pub mod meta;
+
+/// Directory in blog root containing user templates.
+pub const TEMPLATE_DIR: &str = "templates";
 
 macro_rules! load_static_template {
     ($reg:ident, $path:literal, $name:literal) => {{
-        let tpl = match Template::compile(include_str!($path)) {
+        let tpl = match Template::compile_with_name(include_str!($path), $path.to_string()) {
             Ok(it) => it,
-            Err(_) => {
-                panic!("Failed to compile template: {} (path: {})", $name, $path);
+            Err(err) => {
+                panic!("Failed to compile built-in template {}: {}", $name, err);
             }
         };
         $reg.register_template($name, tpl);
@@ -23,9 +30,11 @@ macro_rules! load_static_template {
 
 fn init_engine() -> Handlebars<'static> {
     let mut handlebars = Handlebars::new();
+    helpers::register(&mut handlebars);
 
     load_static_template!(handlebars, "./redirect.hbs", "redirect");
     load_static_template!(handlebars, "./article.hbs", "article");
+    load_static_template!(handlebars, "./layout.hbs", "layout");
 
     handlebars
 }
@@ -43,6 +52,53 @@ pub fn engine() -> &'static mut RwLock<Handlebars<'static>> {
     }
 }
 
+/// Replaces the template engine with a new one, dropping templates and
+/// overrides registered by [`load_dir`].
+pub fn reset() {
+    *engine().write().expect("engine poisoned") = init_engine();
+}
+
+/// Registers all `.hbs` files in `dir` (recursively) with the template
+/// engine.
+///
+/// Templates are named by their path relative to `dir` without extension, so
+/// `article.hbs` overrides the built-in article template and `header.hbs` can
+/// be included by other templates as `{{> header}}`. In `dev_mode` templates
+/// are reloaded from disk whenever they're rendered.
+///
+/// Returns the number of registered templates.
+pub fn load_dir(dir: impl AsRef<Path>, dev_mode: bool) -> Result<usize, FormatError> {
+    let glob = Glob::new("**/*.hbs").expect("invalid template glob");
+
+    let dir = dir.as_ref();
+    let mut engine = engine().write().expect("engine poisoned");
+    engine.set_dev_mode(dev_mode);
+
+    let mut count = 0;
+    for entry in glob.read(dir.to_path_buf(), 8).filter_map(|it| it.ok()) {
+        let path = entry.path();
+        let name = path
+            .strip_prefix(dir)
+            .unwrap_or(path)
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        engine
+            .register_template_file(&name, path)
+            .map_err(|source| FormatError::Template {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        log::debug!("registered template {} ({})", name, path.display());
+        count += 1;
+    }
+
+    Ok(count)
+}
+
 pub trait Generate {
-    fn generate(&self, path: impl AsRef<Path>) -> Result<String, FormatError>;
+    /// Renders the template with `engine` and writes the result to `path`.
+    fn generate(&self, engine: &Handlebars, path: impl AsRef<Path>)
+        -> Result<String, FormatError>;
 }
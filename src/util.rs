@@ -23,4 +23,15 @@ pub fn program(name: &'static str) -> Command {
     // check local
     // check PATH
     Command::new(name)
-}
\ No newline at end of file
+}
+
+/// Returns a short, build independent hash of `content` (64-bit FNV-1a) as a
+/// hex string.
+pub fn content_hash(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in content {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,60 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Time between checks for changed files in watch mode.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Modification times and sizes of watched files, compared to detect changes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Snapshot {
+    /// Records all files in `dirs`.
+    ///
+    /// Hidden files, anything under `exclude` directories (e.g. the target
+    /// directory) and directory symlinks are skipped.
+    pub fn take(dirs: &[PathBuf], exclude: &[PathBuf]) -> Snapshot {
+        let exclude: Vec<PathBuf> = exclude.iter().filter_map(|it| it.canonicalize().ok()).collect();
+        let is_excluded = |path: &Path| {
+            path.canonicalize()
+                .map(|it| exclude.contains(&it))
+                .unwrap_or_default()
+        };
+
+        let mut files = BTreeMap::new();
+        let mut pending: Vec<PathBuf> = dirs.to_vec();
+        while let Some(dir) = pending.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(|it| it.ok()) {
+                let path = entry.path();
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let file_type = match entry.file_type() {
+                    Ok(it) => it,
+                    Err(_) => continue,
+                };
+                if file_type.is_dir() {
+                    if !is_excluded(&path) {
+                        pending.push(path);
+                    }
+                } else if !(file_type.is_symlink() && path.is_dir()) {
+                    let metadata = std::fs::metadata(&path).ok();
+                    let modified = metadata.as_ref().and_then(|it| it.modified().ok());
+                    let len = metadata.map(|it| it.len()).unwrap_or_default();
+                    files.insert(path, (modified, len));
+                }
+            }
+        }
+
+        Snapshot { files }
+    }
+}